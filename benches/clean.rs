@@ -0,0 +1,145 @@
+//! Compares the single-pass cleaning engine with the original regex and `.replace` chain.
+//!
+//! Run with `cargo bench --bench clean`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use module_structure_cleaner::Cleaner;
+
+/// Number of lines in each generated input.
+const LINES: usize = 20_000;
+
+/// Builds colored `cargo modules structure` output with `LINES` lines.
+fn colored_structure() -> String {
+    let kinds = ["mod", "struct", "enum", "trait", "fn", "const"];
+    let colors = ["32", "33", "31", "35"];
+    let mut text = String::new();
+    for index in 0..LINES {
+        let depth = index % 5;
+        text.push_str(&"│   ".repeat(depth));
+        text.push_str(if index % 7 == 0 {
+            "└── "
+        } else {
+            "├── "
+        });
+        text.push_str(&format!(
+            "\x1b[34m{}\x1b[0m \x1b[{}mitem_{}\x1b[0m: \x1b[{}mpub(crate)\x1b[0m\n",
+            kinds[index % kinds.len()],
+            colors[index % colors.len()],
+            index,
+            colors[(index + 1) % colors.len()],
+        ));
+    }
+    text
+}
+
+/// Builds already cleaned, pure-ASCII structure output with `LINES` lines.
+fn ascii_structure() -> String {
+    let mut text = String::new();
+    for index in 0..LINES {
+        text.push_str(&"|   ".repeat(index % 5));
+        text.push_str(&format!("+-- fn item_{}: pub(crate)\n", index));
+    }
+    text
+}
+
+/// The original per-line implementation: a regex compiled per call and a chain of
+/// `.replace` calls that each allocate a new `String`.
+fn legacy_clean_text(input: &str) -> String {
+    let ansi_regex = regex::Regex::new(r"\x1B\[[0-9;]*[a-zA-Z]").unwrap();
+    let no_ansi = ansi_regex.replace_all(input, "");
+
+    no_ansi
+        .replace('├', "+")
+        .replace('─', "-")
+        .replace('│', "|")
+        .replace(['└', '┌', '┐', '┘', '┬', '┴', '┼', '╭', '╮', '╯', '╰'], "+")
+        .replace('╱', "/")
+        .replace('╲', "\\")
+        .replace('╳', "X")
+        .replace('╴', "-")
+        .replace('╵', "|")
+        .replace('╶', "-")
+        .replace('╷', "|")
+        .replace('╸', "-")
+        .replace('╹', "|")
+        .replace('╺', "-")
+        .replace('╻', "|")
+        .replace('╼', "-")
+        .replace('╽', "|")
+        .replace('╾', "-")
+        .replace('╿', "|")
+        .replace('═', "=")
+        .replace('║', "|")
+        .replace(
+            [
+                '╒', '╓', '╔', '╕', '╖', '╗', '╘', '╙', '╚', '╛', '╜', '╝', '╞', '╟', '╠', '╡',
+                '╢', '╣', '╤', '╥', '╦', '╧', '╨', '╩', '╪', '╫', '╬', '╭', '╮', '╯', '╰',
+            ],
+            "+",
+        )
+        .replace('╱', "/")
+        .replace('╲', "\\")
+        .replace('╳', "X")
+        .replace('╴', "-")
+        .replace('╵', "|")
+        .replace('╶', "-")
+        .replace('╷', "|")
+        .replace('╸', "-")
+        .replace('╹', "|")
+        .replace('╺', "-")
+        .replace('╻', "|")
+        .replace('╼', "-")
+        .replace('╽', "|")
+        .replace('╾', "-")
+        .replace('╿', "|")
+        .to_string()
+}
+
+/// The original file loop: `reader.lines()` and `writeln!` per line.
+fn legacy_clean_lines(input: &str) -> Vec<u8> {
+    use std::io::{BufRead, Write};
+
+    let mut output = Vec::with_capacity(input.len());
+    for line in input.as_bytes().lines() {
+        let line = line.unwrap();
+        writeln!(output, "{}", legacy_clean_text(&line)).unwrap();
+    }
+    output
+}
+
+fn bench_clean(c: &mut Criterion) {
+    let cleaner = Cleaner::new();
+    let mut group = c.benchmark_group("clean");
+
+    for (name, input) in [
+        ("colored", colored_structure()),
+        ("ascii", ascii_structure()),
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("legacy", name), &input, |b, input| {
+            b.iter(|| legacy_clean_lines(black_box(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("single_pass", name), &input, |b, input| {
+            let mut output = Vec::with_capacity(input.len());
+            b.iter(|| {
+                output.clear();
+                cleaner
+                    .clean_reader_to_writer(black_box(input.as_bytes()), &mut output)
+                    .unwrap();
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("clean_into", name), &input, |b, input| {
+            let mut output = String::with_capacity(input.len());
+            b.iter(|| {
+                output.clear();
+                cleaner.clean_into(black_box(input), &mut output);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_clean);
+criterion_main!(benches);
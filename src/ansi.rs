@@ -0,0 +1,385 @@
+//! ECMA-48 / VT escape sequence parser.
+//!
+//! The parser is a small state machine that splits text into plain text runs and
+//! escape sequences. It recognizes every control function class of ECMA-48 in both
+//! its 7-bit (`ESC`-prefixed) and 8-bit (C1 control character) representation.
+
+use std::fmt;
+use std::ops::Range;
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{07}';
+const ST_8BIT: char = '\u{9c}';
+
+/// The class of an escape sequence, following the ECMA-48 terminology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SequenceKind {
+    /// Control Sequence Introducer: `ESC [` or `0x9B`, e.g. `ESC[1;32m` or `ESC[?25l`.
+    Csi,
+    /// Operating System Command: `ESC ]` or `0x9D`, terminated by `BEL` or `ST`.
+    Osc,
+    /// Device Control String: `ESC P` or `0x90`, terminated by `ST`.
+    Dcs,
+    /// Start Of String: `ESC X` or `0x98`, terminated by `ST`.
+    Sos,
+    /// Privacy Message: `ESC ^` or `0x9E`, terminated by `ST`.
+    Pm,
+    /// Application Program Command: `ESC _` or `0x9F`, terminated by `ST`.
+    Apc,
+    /// Single shift `ESC N`/`ESC O` or `0x8E`/`0x8F`, together with the shifted character.
+    SingleShift,
+    /// Escape sequence with intermediate bytes (nF), e.g. charset selection `ESC ( B`.
+    Intermediate,
+    /// Private two-byte escape (Fp), e.g. `ESC =` or `ESC 7`.
+    Private,
+    /// Standardized two-byte escape (Fs), e.g. `ESC c`.
+    Standard,
+    /// Any other C1 control function, as `ESC` + `0x40..=0x5F` or as an 8-bit control.
+    C1Control,
+    /// An `ESC` that does not start a valid sequence, e.g. at the end of the input.
+    LoneEscape,
+}
+
+impl SequenceKind {
+    /// Returns the short name of the sequence class, e.g. `"CSI"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            SequenceKind::Csi => "CSI",
+            SequenceKind::Osc => "OSC",
+            SequenceKind::Dcs => "DCS",
+            SequenceKind::Sos => "SOS",
+            SequenceKind::Pm => "PM",
+            SequenceKind::Apc => "APC",
+            SequenceKind::SingleShift => "SS",
+            SequenceKind::Intermediate => "nF",
+            SequenceKind::Private => "Fp",
+            SequenceKind::Standard => "Fs",
+            SequenceKind::C1Control => "C1",
+            SequenceKind::LoneEscape => "ESC",
+        }
+    }
+}
+
+impl fmt::Display for SequenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An escape sequence found in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeSequence<'a> {
+    /// The class of the sequence.
+    pub kind: SequenceKind,
+    /// The complete sequence, including its introducer and terminator.
+    pub raw: &'a str,
+    /// The byte range of the sequence in the input.
+    pub range: Range<usize>,
+    /// `false` if the input ended before the sequence was terminated.
+    pub terminated: bool,
+}
+
+/// A piece of the input: either plain text or an escape sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// A run of text that contains no escape sequences.
+    Text(&'a str),
+    /// A single escape sequence.
+    Escape(EscapeSequence<'a>),
+}
+
+/// Splits `input` into plain text runs and escape sequences.
+///
+/// # Details
+/// - Concatenating the tokens in order reproduces `input` exactly.
+/// - The iterator does not allocate.
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens { input, pos: 0 }
+}
+
+/// Iterator over the [`Token`]s of a string, created by [`tokenize`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.input[self.pos..];
+        if rest.is_empty() {
+            return None;
+        }
+
+        let text_len = find_introducer(rest.as_bytes()).unwrap_or(rest.len());
+        if text_len > 0 {
+            self.pos += text_len;
+            return Some(Token::Text(&rest[..text_len]));
+        }
+
+        let (kind, len, terminated) = parse_sequence(rest);
+        let start = self.pos;
+        self.pos += len;
+        Some(Token::Escape(EscapeSequence {
+            kind,
+            raw: &rest[..len],
+            range: start..self.pos,
+            terminated,
+        }))
+    }
+}
+
+/// Returns the byte offset of the first `ESC` or 8-bit C1 control in UTF-8 `bytes`.
+///
+/// # Details
+/// - C1 controls U+0080 to U+009F are encoded as `0xC2 0x80` to `0xC2 0x9F`, so the
+///   scan works on bytes and never has to decode characters.
+fn find_introducer(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().enumerate().position(|(index, &byte)| {
+        byte == 0x1b || (byte == 0xc2 && matches!(bytes.get(index + 1), Some(0x80..=0x9f)))
+    })
+}
+
+/// Returns `true` if `c` starts an escape sequence: `ESC` or an 8-bit C1 control.
+pub fn is_introducer(c: char) -> bool {
+    c == ESC || ('\u{80}'..='\u{9f}').contains(&c)
+}
+
+/// Returns the length of a complete 8-bit CSI or OSC sequence at the start of `bytes`,
+/// which are not valid UTF-8 there.
+///
+/// # Returns
+/// - `Some(len)` for `0x9B` followed by the parameters and final byte of a CSI, or
+///   `0x9D` followed by printable ASCII and `BEL`.
+/// - `None` for any other bytes.
+///
+/// # Details
+/// - Outside UTF-8, a byte 0x80 to 0x9F is far more often a Windows-1252 character,
+///   such as the curly quote 0x93, than a C1 control. Only complete sequences are
+///   accepted, so a stray 0x98 or 0x9E is not mistaken for a string that swallows
+///   the rest of the line.
+pub fn raw_c1_sequence_len(bytes: &[u8]) -> Option<usize> {
+    let (&introducer, rest) = bytes.split_first()?;
+    let body_end = match introducer {
+        0x9b => {
+            let end = rest.iter().position(|byte| !(0x20..=0x3f).contains(byte))?;
+            (0x40..=0x7e).contains(&rest[end]).then_some(end + 1)?
+        }
+        0x9d => {
+            let end = rest.iter().position(|byte| !(0x20..=0x7e).contains(byte))?;
+            (rest[end] == 0x07).then_some(end + 1)?
+        }
+        _ => return None,
+    };
+    Some(1 + body_end)
+}
+
+/// States of the sequence parser after the introducer has been consumed.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Parameter, intermediate or final bytes of a control sequence.
+    Csi,
+    /// Intermediate bytes of an nF escape sequence.
+    Intermediate,
+    /// Command string (OSC), terminated by `BEL` or `ST`.
+    CommandString,
+    /// Control string (DCS, SOS, PM, APC), terminated by `ST`.
+    ControlString,
+    /// The character shifted by SS2 or SS3.
+    SingleShift,
+}
+
+/// Parses the escape sequence at the start of `input`.
+///
+/// # Parameters
+/// - `input`: Text that starts with an introducer accepted by [`is_introducer`].
+///
+/// # Returns
+/// - The class of the sequence, its length in bytes and whether it was terminated.
+fn parse_sequence(input: &str) -> (SequenceKind, usize, bool) {
+    let mut chars = input.char_indices();
+    let (_, introducer) = chars.next().expect("sequence starts with an introducer");
+
+    // Map the 7-bit `ESC Fe` form onto its 8-bit C1 equivalent
+    let c1 = if introducer == ESC {
+        match chars.next() {
+            Some((_, c @ '\u{40}'..='\u{5f}')) => char::from_u32(c as u32 + 0x40).unwrap(),
+            Some((_, '\u{20}'..='\u{2f}')) => {
+                return run(
+                    input,
+                    chars,
+                    State::Intermediate,
+                    SequenceKind::Intermediate,
+                );
+            }
+            Some((_, '\u{30}'..='\u{3f}')) => return (SequenceKind::Private, 2, true),
+            Some((_, '\u{60}'..='\u{7e}')) => return (SequenceKind::Standard, 2, true),
+            _ => return (SequenceKind::LoneEscape, 1, false),
+        }
+    } else {
+        introducer
+    };
+
+    let (kind, state) = match c1 {
+        '\u{9b}' => (SequenceKind::Csi, State::Csi),
+        '\u{9d}' => (SequenceKind::Osc, State::CommandString),
+        '\u{90}' => (SequenceKind::Dcs, State::ControlString),
+        '\u{98}' => (SequenceKind::Sos, State::ControlString),
+        '\u{9e}' => (SequenceKind::Pm, State::ControlString),
+        '\u{9f}' => (SequenceKind::Apc, State::ControlString),
+        '\u{8e}' | '\u{8f}' => (SequenceKind::SingleShift, State::SingleShift),
+        _ => {
+            let len = if introducer == ESC {
+                2
+            } else {
+                introducer.len_utf8()
+            };
+            return (SequenceKind::C1Control, len, true);
+        }
+    };
+    run(input, chars, state, kind)
+}
+
+/// Runs the state machine over the remainder of a sequence.
+///
+/// # Parameters
+/// - `input`: The complete input, starting at the introducer.
+/// - `chars`: The characters following the already consumed prefix.
+/// - `state`: The state after the prefix.
+/// - `kind`: The class of the sequence.
+///
+/// # Returns
+/// - The class of the sequence, its length in bytes and whether it was terminated.
+fn run(
+    input: &str,
+    chars: std::str::CharIndices<'_>,
+    state: State,
+    kind: SequenceKind,
+) -> (SequenceKind, usize, bool) {
+    let mut after_esc = false;
+
+    for (index, c) in chars {
+        let next = index + c.len_utf8();
+        match state {
+            State::Csi => match c {
+                // Parameter bytes (digits, `;`, `:`, private markers) and intermediate bytes
+                '\u{20}'..='\u{3f}' => {}
+                '\u{40}'..='\u{7e}' => return (kind, next, true),
+                // Anything else aborts the sequence before the offending character
+                _ => return (kind, index, false),
+            },
+            State::Intermediate => match c {
+                '\u{20}'..='\u{2f}' => {}
+                '\u{30}'..='\u{7e}' => return (kind, next, true),
+                _ => return (kind, index, false),
+            },
+            State::CommandString | State::ControlString => {
+                if after_esc {
+                    if c == '\\' {
+                        return (kind, next, true);
+                    }
+                    after_esc = false;
+                }
+                match c {
+                    ESC => after_esc = true,
+                    ST_8BIT => return (kind, next, true),
+                    BEL if matches!(state, State::CommandString) => return (kind, next, true),
+                    _ => {}
+                }
+            }
+            State::SingleShift => return (kind, next, true),
+        }
+    }
+
+    // The input ended before the terminator
+    (kind, input.len(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the only escape sequence of `input`, which must be a whole sequence.
+    fn sequence(input: &str) -> EscapeSequence<'_> {
+        let tokens: Vec<Token> = tokenize(input).collect();
+        match tokens.as_slice() {
+            [Token::Escape(sequence)] => sequence.clone(),
+            tokens => panic!("{:?} is not a single sequence: {:?}", input, tokens),
+        }
+    }
+
+    fn assert_sequence(input: &str, kind: SequenceKind) {
+        let sequence = sequence(input);
+        assert_eq!(sequence.kind, kind, "{:?}", input);
+        assert_eq!(sequence.raw, input);
+        assert_eq!(sequence.range, 0..input.len());
+        assert!(sequence.terminated, "{:?}", input);
+    }
+
+    #[test]
+    fn csi_sequences() {
+        assert_sequence("\u{1b}[1;32m", SequenceKind::Csi);
+        assert_sequence("\u{1b}[?25l", SequenceKind::Csi);
+        assert_sequence("\u{1b}[38:2::255:0:0m", SequenceKind::Csi);
+        // Intermediate byte ` ` in DECSCUSR
+        assert_sequence("\u{1b}[2 q", SequenceKind::Csi);
+        assert_sequence("\u{9b}31m", SequenceKind::Csi);
+    }
+
+    #[test]
+    fn string_sequences() {
+        assert_sequence("\u{1b}]0;title\u{7}", SequenceKind::Osc);
+        assert_sequence("\u{1b}]8;;https://example.com\u{1b}\\", SequenceKind::Osc);
+        assert_sequence("\u{9d}0;title\u{9c}", SequenceKind::Osc);
+        assert_sequence("\u{1b}P1$r0m\u{1b}\\", SequenceKind::Dcs);
+        assert_sequence("\u{1b}_Gf=100\u{1b}\\", SequenceKind::Apc);
+        assert_sequence("\u{1b}^private\u{1b}\\", SequenceKind::Pm);
+        assert_sequence("\u{1b}Xstring\u{1b}\\", SequenceKind::Sos);
+        assert_sequence("\u{90}q\u{9c}", SequenceKind::Dcs);
+    }
+
+    #[test]
+    fn bel_only_terminates_osc() {
+        let sequence = sequence("\u{1b}Pdata\u{7}");
+        assert_eq!(sequence.kind, SequenceKind::Dcs);
+        assert!(!sequence.terminated);
+    }
+
+    #[test]
+    fn short_escape_sequences() {
+        assert_sequence("\u{1b}(B", SequenceKind::Intermediate);
+        assert_sequence("\u{1b}=", SequenceKind::Private);
+        assert_sequence("\u{1b}7", SequenceKind::Private);
+        assert_sequence("\u{1b}c", SequenceKind::Standard);
+        assert_sequence("\u{1b}Nx", SequenceKind::SingleShift);
+        assert_sequence("\u{1b}E", SequenceKind::C1Control);
+        assert_sequence("\u{85}", SequenceKind::C1Control);
+    }
+
+    #[test]
+    fn lone_and_unterminated_escapes() {
+        let lone = sequence("\u{1b}");
+        assert_eq!(lone.kind, SequenceKind::LoneEscape);
+        assert!(!lone.terminated);
+
+        let tokens: Vec<Token> = tokenize("\u{1b}[31\nnext").collect();
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(&tokens[0], Token::Escape(sequence)
+            if sequence.raw == "\u{1b}[31" && !sequence.terminated));
+        assert_eq!(tokens[1], Token::Text("\nnext"));
+    }
+
+    #[test]
+    fn tokens_reproduce_the_input() {
+        let input = "a\u{1b}[1mb\u{9b}0m├─\u{1b}]0;t\u{7}c";
+        let joined: String = tokenize(input)
+            .map(|token| match token {
+                Token::Text(text) => text,
+                Token::Escape(sequence) => sequence.raw,
+            })
+            .collect();
+        assert_eq!(joined, input);
+    }
+}
@@ -0,0 +1,252 @@
+use encoding_rs::Encoding;
+use module_structure_cleaner::encoding;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Number of leading bytes inspected by [`is_binary`].
+const BINARY_SAMPLE_LEN: usize = 8000;
+
+/// Shortest sample without a byte order mark that [`is_binary`] may take for UTF-16.
+const MIN_UTF16_SAMPLE_LEN: usize = 32;
+
+/// Expands the command-line inputs into the list of files to clean.
+///
+/// # Parameters
+/// - `inputs`: Files, directories and glob patterns from the command line.
+/// - `recursive`: Whether directories are searched recursively.
+/// - `suffix`: The output suffix; files named `<stem><suffix>.<ext>` are outputs of
+///   earlier runs and are left out when expanding directories and patterns.
+///
+/// # Returns
+/// - The files in command-line order, each directory and pattern sorted by path.
+/// - The patterns that did not match any file.
+///
+/// # Details
+/// - Files named explicitly are always kept, even if they look like outputs.
+/// - Hidden entries, whose names start with `.`, are skipped inside directories and
+///   only matched by patterns that name the `.` explicitly, as in a shell.
+/// - Patterns are only expanded when no file of that name exists, so shells that
+///   already expanded them are not affected.
+pub fn expand_inputs(
+    inputs: &[PathBuf],
+    recursive: bool,
+    suffix: &str,
+) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut files = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for input in inputs {
+        if input.is_dir() {
+            let mut found = Vec::new();
+            collect_dir(input, recursive, &mut found)?;
+            found.sort();
+            files.extend(found.into_iter().filter(|file| !is_output(file, suffix)));
+        } else if !input.exists() && is_pattern(input) {
+            let pattern = input.to_string_lossy();
+            let options = glob::MatchOptions {
+                require_literal_leading_dot: true,
+                ..glob::MatchOptions::new()
+            };
+            let paths = glob::glob_with(&pattern, options)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            let mut found: Vec<PathBuf> = paths
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file() && !is_output(path, suffix))
+                .collect();
+            found.sort();
+            if found.is_empty() {
+                unmatched.push(input.clone());
+            }
+            files.extend(found);
+        } else {
+            files.push(input.clone());
+        }
+    }
+    Ok((files, unmatched))
+}
+
+/// Returns `true` if the file looks binary: its first 8000 bytes contain a NUL byte.
+///
+/// # Details
+/// - Text declared as UTF-16 with `encoding`, or starting with a UTF-16 byte order
+///   mark, is not binary even though it contains NUL bytes.
+/// - Without either, the NUL bytes are only taken for UTF-16 in samples of at least
+///   32 bytes, so that small binary files are not transcoded as text.
+pub fn is_binary(path: &Path, encoding: Option<&'static Encoding>) -> io::Result<bool> {
+    let mut sample = Vec::with_capacity(BINARY_SAMPLE_LEN);
+    File::open(path)?
+        .take(BINARY_SAMPLE_LEN as u64)
+        .read_to_end(&mut sample)?;
+    let utf16 = match encoding {
+        Some(encoding) => encoding::is_utf16(encoding),
+        None => match Encoding::for_bom(&sample) {
+            Some((encoding, _)) => encoding::is_utf16(encoding),
+            None => {
+                sample.len() >= MIN_UTF16_SAMPLE_LEN
+                    && encoding::is_utf16(encoding::detect(&sample))
+            }
+        },
+    };
+    Ok(sample.contains(&0) && !utf16)
+}
+
+/// The outcome of a batch run, printed at its end.
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Files that were cleaned.
+    pub succeeded: Vec<PathBuf>,
+    /// Files or patterns that failed, with the error message.
+    pub failed: Vec<(PathBuf, String)>,
+    /// Files that were skipped because they contain NUL bytes.
+    pub skipped_binary: Vec<PathBuf>,
+}
+
+impl Summary {
+    /// Prints the report to standard error.
+    ///
+    /// # Details
+    /// - Failures and the totals are always printed when something failed. Skipped
+    ///   files and the totals of a successful run are only printed unless `quiet` is set.
+    pub fn print(&self, quiet: bool) {
+        if !quiet {
+            for path in &self.skipped_binary {
+                eprintln!("skipped (binary): {}", path.display());
+            }
+        }
+        for (path, error) in &self.failed {
+            eprintln!("failed: {}: {}", path.display(), error);
+        }
+        if !quiet || !self.failed.is_empty() {
+            eprintln!("Summary: {}", self.totals());
+        }
+    }
+
+    /// Returns the totals, e.g. `3 succeeded, 0 failed, 1 skipped as binary`.
+    pub fn totals(&self) -> String {
+        format!(
+            "{} succeeded, {} failed, {} skipped as binary",
+            self.succeeded.len(),
+            self.failed.len(),
+            self.skipped_binary.len()
+        )
+    }
+}
+
+fn collect_dir(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            if recursive {
+                collect_dir(&path, recursive, files)?;
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if the path contains glob metacharacters.
+fn is_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Returns `true` if the file is named like an output of this tool.
+pub fn is_output(path: &Path, suffix: &str) -> bool {
+    !suffix.is_empty()
+        && path
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::UTF_16LE;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn nul_bytes_are_binary_unless_utf16() {
+        let dir = temp_dir("batch-binary");
+        let cases: [(&str, Vec<u8>, Option<&'static Encoding>, bool); 7] = [
+            ("text", b"plain text\n".to_vec(), None, false),
+            ("nul", b"ELF\x00\x01\x02".to_vec(), None, true),
+            // Short samples with every other byte NUL are not enough for UTF-16
+            ("short", vec![1, 0, 2, 0, 3, 0], None, true),
+            (
+                "bom",
+                [&[0xff, 0xfe][..], &utf16le("ab")].concat(),
+                None,
+                false,
+            ),
+            ("long", utf16le("├── mod cli: pub(crate)\n"), None, false),
+            ("declared", utf16le("ab"), Some(UTF_16LE), false),
+            ("empty", Vec::new(), None, false),
+        ];
+        for (name, contents, encoding, binary) in cases {
+            let path = dir.join(name);
+            fs::write(&path, contents).unwrap();
+            assert_eq!(is_binary(&path, encoding).unwrap(), binary, "{}", name);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn inputs_are_expanded_in_order() {
+        let dir = temp_dir("batch-expand");
+        fs::create_dir_all(dir.join("sub/deeper")).unwrap();
+        for file in [
+            "b.txt",
+            "a.txt",
+            "a_clean.txt",
+            ".hidden.txt",
+            "notes.md",
+            "sub/c.txt",
+            "sub/deeper/d.txt",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        let pattern = dir.join("*.txt");
+        let missing = dir.join("*.none");
+        let inputs = [
+            dir.join("notes.md"),
+            pattern,
+            dir.join("sub"),
+            missing.clone(),
+        ];
+        let (files, unmatched) = expand_inputs(&inputs, false, "_clean").unwrap();
+        let expected: Vec<PathBuf> = ["notes.md", "a.txt", "b.txt", "sub/c.txt"]
+            .iter()
+            .map(|file| dir.join(file))
+            .collect();
+        assert_eq!(files, expected);
+        assert_eq!(unmatched, [missing]);
+
+        let (files, _) = expand_inputs(&[dir.join("sub")], true, "_clean").unwrap();
+        assert_eq!(files, [dir.join("sub/c.txt"), dir.join("sub/deeper/d.txt")]);
+
+        // Files named explicitly are kept even if they look like outputs
+        let explicit = dir.join("a_clean.txt");
+        let (files, _) = expand_inputs(std::slice::from_ref(&explicit), false, "_clean").unwrap();
+        assert_eq!(files, [explicit]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
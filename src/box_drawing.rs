@@ -0,0 +1,284 @@
+//! ASCII replacements for the Unicode Box Drawing and Block Elements ranges.
+//!
+//! The table covers every code point from U+2500 to U+259F. Lines become `-`, `=`
+//! or `|`, corners, junctions and arcs become `+`, diagonals become `/`, `\` or `X`,
+//! and block elements become a character of similar weight.
+
+/// The first code point covered by [`ASCII_TABLE`].
+pub const FIRST: char = '\u{2500}';
+
+/// The last code point covered by [`ASCII_TABLE`].
+pub const LAST: char = '\u{259f}';
+
+/// ASCII replacement for each code point from [`FIRST`] to [`LAST`], in code point order.
+pub const ASCII_TABLE: [&str; LAST as usize - FIRST as usize + 1] = [
+    // Box Drawing (U+2500..=U+257F)
+    "-",  // U+2500 ─ LIGHT HORIZONTAL
+    "-",  // U+2501 ━ HEAVY HORIZONTAL
+    "|",  // U+2502 │ LIGHT VERTICAL
+    "|",  // U+2503 ┃ HEAVY VERTICAL
+    "-",  // U+2504 ┄ LIGHT TRIPLE DASH HORIZONTAL
+    "-",  // U+2505 ┅ HEAVY TRIPLE DASH HORIZONTAL
+    "|",  // U+2506 ┆ LIGHT TRIPLE DASH VERTICAL
+    "|",  // U+2507 ┇ HEAVY TRIPLE DASH VERTICAL
+    "-",  // U+2508 ┈ LIGHT QUADRUPLE DASH HORIZONTAL
+    "-",  // U+2509 ┉ HEAVY QUADRUPLE DASH HORIZONTAL
+    "|",  // U+250A ┊ LIGHT QUADRUPLE DASH VERTICAL
+    "|",  // U+250B ┋ HEAVY QUADRUPLE DASH VERTICAL
+    "+",  // U+250C ┌ LIGHT DOWN AND RIGHT
+    "+",  // U+250D ┍ DOWN LIGHT AND RIGHT HEAVY
+    "+",  // U+250E ┎ DOWN HEAVY AND RIGHT LIGHT
+    "+",  // U+250F ┏ HEAVY DOWN AND RIGHT
+    "+",  // U+2510 ┐ LIGHT DOWN AND LEFT
+    "+",  // U+2511 ┑ DOWN LIGHT AND LEFT HEAVY
+    "+",  // U+2512 ┒ DOWN HEAVY AND LEFT LIGHT
+    "+",  // U+2513 ┓ HEAVY DOWN AND LEFT
+    "+",  // U+2514 └ LIGHT UP AND RIGHT
+    "+",  // U+2515 ┕ UP LIGHT AND RIGHT HEAVY
+    "+",  // U+2516 ┖ UP HEAVY AND RIGHT LIGHT
+    "+",  // U+2517 ┗ HEAVY UP AND RIGHT
+    "+",  // U+2518 ┘ LIGHT UP AND LEFT
+    "+",  // U+2519 ┙ UP LIGHT AND LEFT HEAVY
+    "+",  // U+251A ┚ UP HEAVY AND LEFT LIGHT
+    "+",  // U+251B ┛ HEAVY UP AND LEFT
+    "+",  // U+251C ├ LIGHT VERTICAL AND RIGHT
+    "+",  // U+251D ┝ VERTICAL LIGHT AND RIGHT HEAVY
+    "+",  // U+251E ┞ UP HEAVY AND RIGHT DOWN LIGHT
+    "+",  // U+251F ┟ DOWN HEAVY AND RIGHT UP LIGHT
+    "+",  // U+2520 ┠ VERTICAL HEAVY AND RIGHT LIGHT
+    "+",  // U+2521 ┡ DOWN LIGHT AND RIGHT UP HEAVY
+    "+",  // U+2522 ┢ UP LIGHT AND RIGHT DOWN HEAVY
+    "+",  // U+2523 ┣ HEAVY VERTICAL AND RIGHT
+    "+",  // U+2524 ┤ LIGHT VERTICAL AND LEFT
+    "+",  // U+2525 ┥ VERTICAL LIGHT AND LEFT HEAVY
+    "+",  // U+2526 ┦ UP HEAVY AND LEFT DOWN LIGHT
+    "+",  // U+2527 ┧ DOWN HEAVY AND LEFT UP LIGHT
+    "+",  // U+2528 ┨ VERTICAL HEAVY AND LEFT LIGHT
+    "+",  // U+2529 ┩ DOWN LIGHT AND LEFT UP HEAVY
+    "+",  // U+252A ┪ UP LIGHT AND LEFT DOWN HEAVY
+    "+",  // U+252B ┫ HEAVY VERTICAL AND LEFT
+    "+",  // U+252C ┬ LIGHT DOWN AND HORIZONTAL
+    "+",  // U+252D ┭ LEFT HEAVY AND RIGHT DOWN LIGHT
+    "+",  // U+252E ┮ RIGHT HEAVY AND LEFT DOWN LIGHT
+    "+",  // U+252F ┯ DOWN LIGHT AND HORIZONTAL HEAVY
+    "+",  // U+2530 ┰ DOWN HEAVY AND HORIZONTAL LIGHT
+    "+",  // U+2531 ┱ RIGHT LIGHT AND LEFT DOWN HEAVY
+    "+",  // U+2532 ┲ LEFT LIGHT AND RIGHT DOWN HEAVY
+    "+",  // U+2533 ┳ HEAVY DOWN AND HORIZONTAL
+    "+",  // U+2534 ┴ LIGHT UP AND HORIZONTAL
+    "+",  // U+2535 ┵ LEFT HEAVY AND RIGHT UP LIGHT
+    "+",  // U+2536 ┶ RIGHT HEAVY AND LEFT UP LIGHT
+    "+",  // U+2537 ┷ UP LIGHT AND HORIZONTAL HEAVY
+    "+",  // U+2538 ┸ UP HEAVY AND HORIZONTAL LIGHT
+    "+",  // U+2539 ┹ RIGHT LIGHT AND LEFT UP HEAVY
+    "+",  // U+253A ┺ LEFT LIGHT AND RIGHT UP HEAVY
+    "+",  // U+253B ┻ HEAVY UP AND HORIZONTAL
+    "+",  // U+253C ┼ LIGHT VERTICAL AND HORIZONTAL
+    "+",  // U+253D ┽ LEFT HEAVY AND RIGHT VERTICAL LIGHT
+    "+",  // U+253E ┾ RIGHT HEAVY AND LEFT VERTICAL LIGHT
+    "+",  // U+253F ┿ VERTICAL LIGHT AND HORIZONTAL HEAVY
+    "+",  // U+2540 ╀ UP HEAVY AND DOWN HORIZONTAL LIGHT
+    "+",  // U+2541 ╁ DOWN HEAVY AND UP HORIZONTAL LIGHT
+    "+",  // U+2542 ╂ VERTICAL HEAVY AND HORIZONTAL LIGHT
+    "+",  // U+2543 ╃ LEFT UP HEAVY AND RIGHT DOWN LIGHT
+    "+",  // U+2544 ╄ RIGHT UP HEAVY AND LEFT DOWN LIGHT
+    "+",  // U+2545 ╅ LEFT DOWN HEAVY AND RIGHT UP LIGHT
+    "+",  // U+2546 ╆ RIGHT DOWN HEAVY AND LEFT UP LIGHT
+    "+",  // U+2547 ╇ DOWN LIGHT AND UP HORIZONTAL HEAVY
+    "+",  // U+2548 ╈ UP LIGHT AND DOWN HORIZONTAL HEAVY
+    "+",  // U+2549 ╉ RIGHT LIGHT AND LEFT VERTICAL HEAVY
+    "+",  // U+254A ╊ LEFT LIGHT AND RIGHT VERTICAL HEAVY
+    "+",  // U+254B ╋ HEAVY VERTICAL AND HORIZONTAL
+    "-",  // U+254C ╌ LIGHT DOUBLE DASH HORIZONTAL
+    "-",  // U+254D ╍ HEAVY DOUBLE DASH HORIZONTAL
+    "|",  // U+254E ╎ LIGHT DOUBLE DASH VERTICAL
+    "|",  // U+254F ╏ HEAVY DOUBLE DASH VERTICAL
+    "=",  // U+2550 ═ DOUBLE HORIZONTAL
+    "|",  // U+2551 ║ DOUBLE VERTICAL
+    "+",  // U+2552 ╒ DOWN SINGLE AND RIGHT DOUBLE
+    "+",  // U+2553 ╓ DOWN DOUBLE AND RIGHT SINGLE
+    "+",  // U+2554 ╔ DOUBLE DOWN AND RIGHT
+    "+",  // U+2555 ╕ DOWN SINGLE AND LEFT DOUBLE
+    "+",  // U+2556 ╖ DOWN DOUBLE AND LEFT SINGLE
+    "+",  // U+2557 ╗ DOUBLE DOWN AND LEFT
+    "+",  // U+2558 ╘ UP SINGLE AND RIGHT DOUBLE
+    "+",  // U+2559 ╙ UP DOUBLE AND RIGHT SINGLE
+    "+",  // U+255A ╚ DOUBLE UP AND RIGHT
+    "+",  // U+255B ╛ UP SINGLE AND LEFT DOUBLE
+    "+",  // U+255C ╜ UP DOUBLE AND LEFT SINGLE
+    "+",  // U+255D ╝ DOUBLE UP AND LEFT
+    "+",  // U+255E ╞ VERTICAL SINGLE AND RIGHT DOUBLE
+    "+",  // U+255F ╟ VERTICAL DOUBLE AND RIGHT SINGLE
+    "+",  // U+2560 ╠ DOUBLE VERTICAL AND RIGHT
+    "+",  // U+2561 ╡ VERTICAL SINGLE AND LEFT DOUBLE
+    "+",  // U+2562 ╢ VERTICAL DOUBLE AND LEFT SINGLE
+    "+",  // U+2563 ╣ DOUBLE VERTICAL AND LEFT
+    "+",  // U+2564 ╤ DOWN SINGLE AND HORIZONTAL DOUBLE
+    "+",  // U+2565 ╥ DOWN DOUBLE AND HORIZONTAL SINGLE
+    "+",  // U+2566 ╦ DOUBLE DOWN AND HORIZONTAL
+    "+",  // U+2567 ╧ UP SINGLE AND HORIZONTAL DOUBLE
+    "+",  // U+2568 ╨ UP DOUBLE AND HORIZONTAL SINGLE
+    "+",  // U+2569 ╩ DOUBLE UP AND HORIZONTAL
+    "+",  // U+256A ╪ VERTICAL SINGLE AND HORIZONTAL DOUBLE
+    "+",  // U+256B ╫ VERTICAL DOUBLE AND HORIZONTAL SINGLE
+    "+",  // U+256C ╬ DOUBLE VERTICAL AND HORIZONTAL
+    "+",  // U+256D ╭ LIGHT ARC DOWN AND RIGHT
+    "+",  // U+256E ╮ LIGHT ARC DOWN AND LEFT
+    "+",  // U+256F ╯ LIGHT ARC UP AND LEFT
+    "+",  // U+2570 ╰ LIGHT ARC UP AND RIGHT
+    "/",  // U+2571 ╱ LIGHT DIAGONAL UPPER RIGHT TO LOWER LEFT
+    "\\", // U+2572 ╲ LIGHT DIAGONAL UPPER LEFT TO LOWER RIGHT
+    "X",  // U+2573 ╳ LIGHT DIAGONAL CROSS
+    "-",  // U+2574 ╴ LIGHT LEFT
+    "|",  // U+2575 ╵ LIGHT UP
+    "-",  // U+2576 ╶ LIGHT RIGHT
+    "|",  // U+2577 ╷ LIGHT DOWN
+    "-",  // U+2578 ╸ HEAVY LEFT
+    "|",  // U+2579 ╹ HEAVY UP
+    "-",  // U+257A ╺ HEAVY RIGHT
+    "|",  // U+257B ╻ HEAVY DOWN
+    "-",  // U+257C ╼ LIGHT LEFT AND HEAVY RIGHT
+    "|",  // U+257D ╽ LIGHT UP AND HEAVY DOWN
+    "-",  // U+257E ╾ HEAVY LEFT AND LIGHT RIGHT
+    "|",  // U+257F ╿ HEAVY UP AND LIGHT DOWN
+    // Block Elements (U+2580..=U+259F)
+    "#",  // U+2580 ▀ UPPER HALF BLOCK
+    "_",  // U+2581 ▁ LOWER ONE EIGHTH BLOCK
+    "_",  // U+2582 ▂ LOWER ONE QUARTER BLOCK
+    "_",  // U+2583 ▃ LOWER THREE EIGHTHS BLOCK
+    "#",  // U+2584 ▄ LOWER HALF BLOCK
+    "#",  // U+2585 ▅ LOWER FIVE EIGHTHS BLOCK
+    "#",  // U+2586 ▆ LOWER THREE QUARTERS BLOCK
+    "#",  // U+2587 ▇ LOWER SEVEN EIGHTHS BLOCK
+    "#",  // U+2588 █ FULL BLOCK
+    "#",  // U+2589 ▉ LEFT SEVEN EIGHTHS BLOCK
+    "#",  // U+258A ▊ LEFT THREE QUARTERS BLOCK
+    "#",  // U+258B ▋ LEFT FIVE EIGHTHS BLOCK
+    "#",  // U+258C ▌ LEFT HALF BLOCK
+    "|",  // U+258D ▍ LEFT THREE EIGHTHS BLOCK
+    "|",  // U+258E ▎ LEFT ONE QUARTER BLOCK
+    "|",  // U+258F ▏ LEFT ONE EIGHTH BLOCK
+    "#",  // U+2590 ▐ RIGHT HALF BLOCK
+    ".",  // U+2591 ░ LIGHT SHADE
+    ":",  // U+2592 ▒ MEDIUM SHADE
+    "#",  // U+2593 ▓ DARK SHADE
+    "-",  // U+2594 ▔ UPPER ONE EIGHTH BLOCK
+    "|",  // U+2595 ▕ RIGHT ONE EIGHTH BLOCK
+    ".",  // U+2596 ▖ QUADRANT LOWER LEFT
+    ".",  // U+2597 ▗ QUADRANT LOWER RIGHT
+    ".",  // U+2598 ▘ QUADRANT UPPER LEFT
+    "#",  // U+2599 ▙ QUADRANT UPPER LEFT AND LOWER LEFT AND LOWER RIGHT
+    "\\", // U+259A ▚ QUADRANT UPPER LEFT AND LOWER RIGHT
+    "#",  // U+259B ▛ QUADRANT UPPER LEFT AND UPPER RIGHT AND LOWER LEFT
+    "#",  // U+259C ▜ QUADRANT UPPER LEFT AND UPPER RIGHT AND LOWER RIGHT
+    ".",  // U+259D ▝ QUADRANT UPPER RIGHT
+    "/",  // U+259E ▞ QUADRANT UPPER RIGHT AND LOWER LEFT
+    "#",  // U+259F ▟ QUADRANT UPPER RIGHT AND LOWER LEFT AND LOWER RIGHT
+];
+
+// Every entry must be a non-empty ASCII string
+const _: () = {
+    let mut index = 0;
+    while index < ASCII_TABLE.len() {
+        let bytes = ASCII_TABLE[index].as_bytes();
+        assert!(!bytes.is_empty());
+        let mut byte = 0;
+        while byte < bytes.len() {
+            assert!(bytes[byte].is_ascii());
+            byte += 1;
+        }
+        index += 1;
+    }
+};
+
+/// Returns the ASCII replacement for a box-drawing or block element character.
+///
+/// # Parameters
+/// - `c`: The character to look up.
+///
+/// # Returns
+/// - `Some(&str)` with the replacement if `c` lies between [`FIRST`] and [`LAST`].
+/// - `None` for every other character.
+pub fn ascii_replacement(c: char) -> Option<&'static str> {
+    (FIRST..=LAST)
+        .contains(&c)
+        .then(|| ASCII_TABLE[c as usize - FIRST as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cleaner;
+
+    /// The expected replacements, one row of 16 code points from U+2500 to U+259F.
+    const EXPECTED: [&str; 10] = [
+        "--||--||--||++++",
+        "++++++++++++++++",
+        "++++++++++++++++",
+        "++++++++++++++++",
+        "++++++++++++--||",
+        "=|++++++++++++++",
+        "++++++++++++++++",
+        r"+/\X-|-|-|-|-|-|",
+        "#___#########|||",
+        r"#.:#-|...#\##./#",
+    ];
+
+    fn row(index: usize) -> impl Iterator<Item = char> {
+        let start = FIRST as u32 + index as u32 * 16;
+        (start..start + 16).map(|code| char::from_u32(code).unwrap())
+    }
+
+    #[test]
+    fn every_code_point_is_cleaned_to_its_replacement() {
+        let cleaner = Cleaner::new();
+        for (index, expected) in EXPECTED.iter().enumerate() {
+            for (c, replacement) in row(index).zip(expected.chars()) {
+                assert_eq!(
+                    cleaner.clean_str(&c.to_string()),
+                    replacement.to_string(),
+                    "U+{:04X}",
+                    c as u32
+                );
+                assert_eq!(ascii_replacement(c), Some(&*replacement.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn whole_rows_are_cleaned_in_one_pass() {
+        let cleaner = Cleaner::new();
+        for (index, expected) in EXPECTED.iter().enumerate() {
+            let input: String = row(index).collect();
+            assert_eq!(cleaner.clean_str(&input), *expected);
+        }
+    }
+
+    #[test]
+    fn table_ends_at_last() {
+        assert_eq!(row(EXPECTED.len() - 1).last(), Some(LAST));
+        assert_eq!(ascii_replacement('\u{24ff}'), None);
+        assert_eq!(ascii_replacement('\u{25a0}'), None);
+    }
+
+    #[test]
+    fn mappings_of_clean_text_are_kept() {
+        let cleaner = Cleaner::new();
+        let cases = [
+            ('├', "+"),
+            ('─', "-"),
+            ('│', "|"),
+            ('└', "+"),
+            ('╱', "/"),
+            ('╲', "\\"),
+            ('╳', "X"),
+            ('═', "="),
+            ('║', "|"),
+            ('╔', "+"),
+        ];
+        for (c, replacement) in cases {
+            assert_eq!(cleaner.clean_str(&c.to_string()), replacement, "{}", c);
+        }
+        assert_eq!(cleaner.clean_str("├── fn main"), "+-- fn main");
+    }
+}
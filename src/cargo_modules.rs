@@ -0,0 +1,116 @@
+//! Runs `cargo modules structure` on a local crate.
+//!
+//! The captured output is the raw, colored tree that the [`Cleaner`](crate::Cleaner)
+//! and the [`ModuleTree`](crate::tree::ModuleTree) parser accept.
+
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Options passed through to `cargo modules structure`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StructureOptions {
+    /// The package to analyze in a workspace, `--package`.
+    pub package: Option<String>,
+    /// Analyze the library target, `--lib`.
+    pub lib: bool,
+    /// The binary target to analyze, `--bin`.
+    pub bin: Option<String>,
+    /// Analyze with `#[cfg(test)]` enabled, `--cfg-test`.
+    pub cfg_test: bool,
+}
+
+impl StructureOptions {
+    /// Returns the command-line arguments after `cargo modules structure`.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(package) = &self.package {
+            args.push("--package".to_string());
+            args.push(package.clone());
+        }
+        if self.lib {
+            args.push("--lib".to_string());
+        }
+        if let Some(bin) = &self.bin {
+            args.push("--bin".to_string());
+            args.push(bin.clone());
+        }
+        if self.cfg_test {
+            args.push("--cfg-test".to_string());
+        }
+        args
+    }
+}
+
+/// Runs `cargo modules structure` in `crate_dir` and returns its standard output.
+///
+/// # Parameters
+/// - `crate_dir`: A crate or workspace directory, or the path of its `Cargo.toml`.
+/// - `options`: The options passed through to cargo-modules.
+///
+/// # Returns
+/// - `Ok(String)` with the captured output, colors included.
+/// - `Err(io::Error)` of kind `NotFound` if cargo or cargo-modules is not installed.
+/// - `Err(io::Error)` of kind `Other` if cargo-modules fails.
+///
+/// # Details
+/// - Standard error is passed through, so cargo's progress and error messages stay
+///   visible while the crate is analyzed.
+/// - Invalid UTF-8 in the output is replaced with U+FFFD.
+pub fn structure(crate_dir: &Path, options: &StructureOptions) -> io::Result<String> {
+    let crate_dir = match crate_dir.file_name() {
+        Some(name) if name == "Cargo.toml" => crate_dir.parent().unwrap_or(Path::new(".")),
+        _ => crate_dir,
+    };
+    let crate_dir = if crate_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        crate_dir
+    };
+    ensure_installed()?;
+
+    let output = Command::new("cargo")
+        .current_dir(crate_dir)
+        .args(["modules", "structure"])
+        .args(options.args())
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "`cargo modules structure` failed with {}",
+            output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Checks that the `cargo modules` subcommand can be run.
+///
+/// # Returns
+/// - `Ok(())` if `cargo modules --version` succeeds.
+/// - `Err(io::Error)` of kind `NotFound` with installation instructions otherwise.
+fn ensure_installed() -> io::Result<()> {
+    let not_installed = |message: &str| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "{}; install cargo-modules with `cargo install cargo-modules`",
+                message
+            ),
+        )
+    };
+
+    let output = Command::new("cargo")
+        .args(["modules", "--version"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => not_installed("cargo was not found"),
+            _ => error,
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(not_installed("cargo-modules is not installed"))
+    }
+}
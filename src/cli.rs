@@ -0,0 +1,414 @@
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use encoding_rs::Encoding;
+use module_structure_cleaner::cargo_modules::StructureOptions;
+use module_structure_cleaner::diagram::{DiagramOptions, StyleBy};
+use module_structure_cleaner::encoding;
+use module_structure_cleaner::filter::{PathPattern, TreeFilter};
+use module_structure_cleaner::html::HtmlOptions;
+use module_structure_cleaner::mapping::PRESETS;
+use module_structure_cleaner::tree::ItemKind;
+use module_structure_cleaner::LineEnding;
+use std::path::PathBuf;
+
+/// Command-line arguments of the cleaner.
+///
+/// # Details
+/// - When no input paths are given and standard input is a pipe, or the only input
+///   is `-`, the program runs as a filter from standard input to standard output.
+/// - When no input paths are given and a display is available, the program
+///   falls back to the interactive file dialog.
+/// - Every input gets its own output file unless `--output` or `--stdout` is used.
+/// - Subcommands take their own inputs; the mapping options still apply to them.
+#[derive(Debug, Clone, Parser)]
+#[command(
+    name = "module_structure_cleaner",
+    version,
+    about = "Removes ANSI escape codes and replaces Unicode box-drawing characters with ASCII"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Input files, directories or glob patterns to clean, or `-` to read from standard input.
+    #[arg(value_name = "INPUT")]
+    pub inputs: Vec<PathBuf>,
+
+    /// Search input directories recursively.
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Keep running and clean the inputs again whenever they change.
+    #[arg(short, long)]
+    pub watch: bool,
+
+    /// Write the cleaned text to this file instead of `<stem><suffix>.<extension>`.
+    #[arg(short, long, value_name = "FILE", conflicts_with = "stdout")]
+    pub output: Option<PathBuf>,
+
+    /// Write the cleaned text to standard output.
+    #[arg(long)]
+    pub stdout: bool,
+
+    /// Replace each input file with its cleaned text, atomically.
+    #[arg(short, long, conflicts_with_all = ["output", "stdout"])]
+    pub in_place: bool,
+
+    /// Keep a copy of each original file as `<file><SUFFIX>` when cleaning in place.
+    #[arg(long, value_name = "SUFFIX", requires = "in_place")]
+    pub backup_suffix: Option<String>,
+
+    /// Output format: cleaned text, the parsed module tree as JSON or YAML, a diagram,
+    /// or an HTML page that keeps the colors.
+    #[arg(
+        long,
+        visible_alias = "to",
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t = Format::Text
+    )]
+    pub format: Format,
+
+    /// Encoding of the input, e.g. `utf-16le` or `windows-1252` [default: detected].
+    #[arg(long, value_name = "ENCODING", value_parser = parse_encoding)]
+    pub input_encoding: Option<&'static Encoding>,
+
+    /// Encoding of the output, e.g. `utf-16le` or `windows-1252` [default: utf-8].
+    #[arg(long, value_name = "ENCODING", value_parser = parse_encoding)]
+    pub output_encoding: Option<&'static Encoding>,
+
+    /// Append the visibility that cargo-modules colors stand for, e.g. `[pub(crate)]`.
+    #[arg(long)]
+    pub markers: bool,
+
+    /// TOML file mapping colors to visibility markers; implies `--markers`.
+    #[arg(long, value_name = "FILE")]
+    pub marker_colors: Option<PathBuf>,
+
+    /// Replace invalid UTF-8 with U+FFFD, or with `--lossy=PLACEHOLDER`, instead of failing.
+    #[arg(
+        long,
+        value_name = "PLACEHOLDER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "\u{FFFD}"
+    )]
+    pub lossy: Option<String>,
+
+    /// Convert line endings instead of keeping those of the input.
+    #[arg(long, value_enum, value_name = "EOL")]
+    pub eol: Option<Eol>,
+
+    /// Replace box-drawing characters with ASCII in HTML output too.
+    #[arg(long)]
+    pub html_ascii: bool,
+
+    /// Style HTML output with inline `style` attributes instead of a stylesheet.
+    #[arg(long)]
+    pub inline_styles: bool,
+
+    /// Color diagram nodes by item kind or visibility.
+    #[arg(long, value_enum, value_name = "PROPERTY")]
+    pub color_by: Option<StyleKey>,
+
+    /// Shape diagram nodes by item kind or visibility.
+    #[arg(long, value_enum, value_name = "PROPERTY")]
+    pub shape_by: Option<StyleKey>,
+
+    /// Keep only `pub` items and their ancestors.
+    #[arg(long)]
+    pub only_pub: bool,
+
+    /// Keep only items of these kinds and their ancestors, e.g. `mod,struct,trait`;
+    /// `other` selects every kind cargo-modules prints that is not listed.
+    #[arg(
+        long,
+        value_name = "KINDS",
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(ItemKind::KEYWORDS.iter().copied().chain(["other"]))
+    )]
+    pub kinds: Vec<String>,
+
+    /// Keep only items whose path matches this glob, e.g. `crate::cli::**`. Repeatable.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Remove items whose path matches this glob, e.g. `crate::tests::**`. Repeatable.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Hide everything below this depth, the crate root having depth 0.
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Hide everything below items whose path matches this glob. Repeatable.
+    #[arg(long, value_name = "PATH")]
+    pub collapse: Vec<String>,
+
+    /// Suffix appended to the input file stem when naming output files.
+    #[arg(long, value_name = "SUFFIX", default_value = "_output")]
+    pub suffix: String,
+
+    /// Named character mapping preset [default: plus].
+    #[arg(long, value_name = "NAME", value_parser = PossibleValuesParser::new(PRESETS))]
+    pub preset: Option<String>,
+
+    /// TOML mapping file that overrides or extends the preset per character.
+    #[arg(long, value_name = "FILE")]
+    pub map: Option<PathBuf>,
+
+    /// Keep escape sequences such as colors and only replace box-drawing characters.
+    #[arg(long, conflicts_with = "keep_boxes")]
+    pub keep_escapes: bool,
+
+    /// Keep Unicode box-drawing characters and only remove escape sequences.
+    #[arg(long)]
+    pub keep_boxes: bool,
+
+    /// Print every removed escape sequence with its position to standard error.
+    #[arg(long)]
+    pub report_escapes: bool,
+
+    /// Only print errors.
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// Output formats selected with `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Cleaned text.
+    Text,
+    /// The parsed module tree as a JSON document.
+    Json,
+    /// The parsed module tree as a YAML document.
+    Yaml,
+    /// A Graphviz DOT digraph.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
+    /// A Mermaid mindmap.
+    MermaidMindmap,
+    /// A PlantUML package diagram.
+    Plantuml,
+    /// A standalone HTML page with the colors kept as styled spans.
+    Html,
+}
+
+impl Format {
+    /// Returns the file extension used when naming output files.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Dot => "dot",
+            Format::Mermaid | Format::MermaidMindmap => "mmd",
+            Format::Plantuml => "puml",
+            Format::Html => "html",
+        }
+    }
+}
+
+/// Subcommands of the cleaner.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Compare two module-structure snapshots, raw or cleaned.
+    Diff(DiffArgs),
+    /// Run `cargo modules structure` on a local crate and clean its output.
+    Generate(GenerateArgs),
+    /// Report `.rs` files under `src/` that no module reaches and modules whose file is missing.
+    CheckOrphans(CheckOrphansArgs),
+}
+
+/// Arguments of the `diff` subcommand.
+#[derive(Debug, Clone, Args)]
+pub struct DiffArgs {
+    /// The old snapshot of `cargo modules structure` output.
+    pub old: PathBuf,
+
+    /// The new snapshot of `cargo modules structure` output.
+    pub new: PathBuf,
+
+    /// Output format of the diff.
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = DiffFormat::Tree)]
+    pub format: DiffFormat,
+
+    /// Write the diff to this file instead of standard output.
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+/// Arguments of the `generate` subcommand.
+///
+/// # Details
+/// - The output is written in the format selected with `--format` and the tree
+///   filters apply, the same as for input files.
+#[derive(Debug, Clone, Args)]
+pub struct GenerateArgs {
+    /// The crate or workspace directory, or its `Cargo.toml`.
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub path: PathBuf,
+
+    /// Package to analyze in a workspace.
+    #[arg(short, long, value_name = "SPEC")]
+    pub package: Option<String>,
+
+    /// Analyze the library target.
+    #[arg(long, conflicts_with = "bin")]
+    pub lib: bool,
+
+    /// Analyze the named binary target.
+    #[arg(long, value_name = "NAME")]
+    pub bin: Option<String>,
+
+    /// Analyze with `#[cfg(test)]` enabled.
+    #[arg(long)]
+    pub cfg_test: bool,
+
+    /// Parse the sources with the built-in extractor instead of running cargo-modules.
+    #[arg(long, conflicts_with = "package")]
+    pub native: bool,
+
+    /// Write the output to this file instead of standard output.
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+impl GenerateArgs {
+    /// Returns the options passed through to cargo-modules.
+    pub fn structure_options(&self) -> StructureOptions {
+        StructureOptions {
+            package: self.package.clone(),
+            lib: self.lib,
+            bin: self.bin.clone(),
+            cfg_test: self.cfg_test,
+        }
+    }
+}
+
+/// Arguments of the `check-orphans` subcommand.
+#[derive(Debug, Clone, Args)]
+pub struct CheckOrphansArgs {
+    /// The crate directory, or its `Cargo.toml`.
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub path: PathBuf,
+}
+
+/// Output formats of the `diff` subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// The new tree with every line marked as added, removed, moved or changed.
+    Tree,
+    /// A unified-style text diff with one section per kind of change.
+    Unified,
+    /// A JSON document listing the changes.
+    Json,
+}
+
+/// Line endings selected with `--eol`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Eol {
+    /// `\n`.
+    Lf,
+    /// `\r\n`.
+    Crlf,
+    /// The line ending of the current platform.
+    Native,
+}
+
+impl From<Eol> for LineEnding {
+    fn from(eol: Eol) -> Self {
+        match eol {
+            Eol::Lf => LineEnding::Lf,
+            Eol::Crlf => LineEnding::CrLf,
+            Eol::Native => LineEnding::native(),
+        }
+    }
+}
+
+/// Node properties accepted by `--color-by` and `--shape-by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StyleKey {
+    /// The item kind, e.g. `mod` or `struct`.
+    Kind,
+    /// The item visibility, e.g. `pub` or `pub(crate)`.
+    Visibility,
+}
+
+impl From<StyleKey> for StyleBy {
+    fn from(key: StyleKey) -> Self {
+        match key {
+            StyleKey::Kind => StyleBy::Kind,
+            StyleKey::Visibility => StyleBy::Visibility,
+        }
+    }
+}
+
+impl Cli {
+    /// Returns the diagram options selected by `--color-by` and `--shape-by`.
+    pub fn diagram_options(&self) -> DiagramOptions {
+        DiagramOptions {
+            color_by: self.color_by.map(Into::into),
+            shape_by: self.shape_by.map(Into::into),
+        }
+    }
+
+    /// Returns the HTML options selected by `--inline-styles`.
+    pub fn html_options(&self) -> HtmlOptions {
+        HtmlOptions {
+            inline_styles: self.inline_styles,
+            ..HtmlOptions::default()
+        }
+    }
+
+    /// Returns the tree filter selected by the filter and pruning options.
+    pub fn tree_filter(&self) -> TreeFilter {
+        TreeFilter {
+            only_pub: self.only_pub,
+            kinds: self
+                .kinds
+                .iter()
+                .map(|kind| ItemKind::from_keyword(kind))
+                .collect(),
+            include: self
+                .include
+                .iter()
+                .map(|glob| PathPattern::new(glob))
+                .collect(),
+            exclude: self
+                .exclude
+                .iter()
+                .map(|glob| PathPattern::new(glob))
+                .collect(),
+            max_depth: self.max_depth,
+            collapse: self
+                .collapse
+                .iter()
+                .map(|glob| PathPattern::new(glob))
+                .collect(),
+        }
+    }
+}
+
+/// Parses an encoding label for `--input-encoding` and `--output-encoding`.
+fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    encoding::for_label(label).ok_or_else(|| format!("unknown encoding `{}`", label))
+}
+
+/// Returns `true` when a graphical file dialog can be shown.
+///
+/// # Details
+/// - On Linux and the BSDs a dialog needs an X11 or Wayland display.
+/// - On Windows and macOS a desktop session is assumed unless the process
+///   runs inside an SSH session.
+#[cfg(feature = "gui")]
+pub fn display_available() -> bool {
+    let set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        !set("SSH_CONNECTION") && !set("SSH_TTY")
+    } else {
+        set("DISPLAY") || set("WAYLAND_DISPLAY")
+    }
+}
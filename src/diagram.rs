@@ -0,0 +1,539 @@
+//! Graphviz DOT, Mermaid and PlantUML renderers for the module tree.
+//!
+//! Every renderer labels nodes with their kind, name and visibility, and can color
+//! or shape nodes by kind or by visibility through [`DiagramOptions`].
+
+use crate::render;
+use crate::tree::{ItemKind, ModuleTree, Node, Visibility};
+use std::fmt::Write;
+
+/// The node property that decides a color or a shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleBy {
+    /// Style nodes by their [`ItemKind`].
+    Kind,
+    /// Style nodes by their [`Visibility`].
+    Visibility,
+}
+
+/// Options shared by all diagram renderers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiagramOptions {
+    /// Colors nodes by kind or visibility, `None` for the renderer's default colors.
+    pub color_by: Option<StyleBy>,
+    /// Shapes nodes by kind or visibility, `None` for the renderer's default shape.
+    pub shape_by: Option<StyleBy>,
+}
+
+/// The style classes a node falls into.
+///
+/// # Details
+/// - Each class has a fixed color and a fixed shape per renderer, so the same node
+///   looks alike in every diagram format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Crate,
+    Mod,
+    Type,
+    Enum,
+    Trait,
+    Fn,
+    Value,
+    Macro,
+    OtherKind,
+    Public,
+    CrateVisible,
+    Restricted,
+    Private,
+    Orphan,
+    NoVisibility,
+}
+
+impl Class {
+    fn of(node: &Node, by: StyleBy) -> Self {
+        match by {
+            StyleBy::Kind => match node.kind {
+                ItemKind::Crate => Class::Crate,
+                ItemKind::Mod => Class::Mod,
+                ItemKind::Struct | ItemKind::Union | ItemKind::Type => Class::Type,
+                ItemKind::Enum => Class::Enum,
+                ItemKind::Trait => Class::Trait,
+                ItemKind::Fn => Class::Fn,
+                ItemKind::Const | ItemKind::Static => Class::Value,
+                ItemKind::Macro => Class::Macro,
+                ItemKind::Other(_) => Class::OtherKind,
+            },
+            StyleBy::Visibility => match &node.visibility {
+                _ if node.orphan => Class::Orphan,
+                Some(Visibility::Public) => Class::Public,
+                Some(Visibility::Crate) => Class::CrateVisible,
+                Some(Visibility::Super | Visibility::Restricted(_)) => Class::Restricted,
+                Some(Visibility::Private) => Class::Private,
+                None => Class::NoVisibility,
+            },
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Class::Crate => "crate",
+            Class::Mod => "mod",
+            Class::Type => "type",
+            Class::Enum => "enum",
+            Class::Trait => "trait",
+            Class::Fn => "fn",
+            Class::Value => "value",
+            Class::Macro => "macro",
+            Class::OtherKind => "other",
+            Class::Public => "pub",
+            Class::CrateVisible => "pub-crate",
+            Class::Restricted => "pub-restricted",
+            Class::Private => "private",
+            Class::Orphan => "orphan",
+            Class::NoVisibility => "none",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Class::Crate => "#4e79a7",
+            Class::Mod => "#f28e2b",
+            Class::Type => "#59a14f",
+            Class::Enum => "#76b7b2",
+            Class::Trait => "#edc948",
+            Class::Fn => "#b07aa1",
+            Class::Value => "#9c755f",
+            Class::Macro => "#ff9da7",
+            Class::OtherKind | Class::NoVisibility => "#bab0ac",
+            Class::Public => "#59a14f",
+            Class::CrateVisible => "#edc948",
+            Class::Restricted => "#f28e2b",
+            Class::Private => "#e15759",
+            Class::Orphan => "#af7aa1",
+        }
+    }
+
+    fn dot_shape(self) -> &'static str {
+        match self {
+            Class::Crate => "doubleoctagon",
+            Class::Mod => "folder",
+            Class::Type | Class::Public => "box",
+            Class::Enum | Class::Restricted => "hexagon",
+            Class::Trait => "diamond",
+            Class::Fn | Class::CrateVisible => "ellipse",
+            Class::Value => "note",
+            Class::Macro => "cds",
+            Class::Private => "octagon",
+            Class::Orphan => "tripleoctagon",
+            Class::OtherKind | Class::NoVisibility => "plain",
+        }
+    }
+
+    /// Opening and closing delimiters of a Mermaid flowchart node.
+    fn mermaid_shape(self) -> (&'static str, &'static str) {
+        match self {
+            Class::Crate => ("[[", "]]"),
+            Class::Mod => ("[/", "\\]"),
+            Class::Type | Class::Public => ("[", "]"),
+            Class::Enum | Class::Restricted => ("{{", "}}"),
+            Class::Trait => ("{", "}"),
+            Class::Fn | Class::CrateVisible => ("([", "])"),
+            Class::Value => ("[(", ")]"),
+            Class::Macro => (">", "]"),
+            Class::Private => ("((", "))"),
+            Class::Orphan => ("(((", ")))"),
+            Class::OtherKind | Class::NoVisibility => ("(", ")"),
+        }
+    }
+
+    /// Opening and closing delimiters of a Mermaid mindmap node.
+    fn mindmap_shape(self) -> (&'static str, &'static str) {
+        match self {
+            Class::Crate | Class::Private => ("((", "))"),
+            Class::Mod | Class::Type | Class::Public => ("[", "]"),
+            Class::Enum | Class::Trait | Class::Restricted => ("{{", "}}"),
+            Class::Fn | Class::Value | Class::CrateVisible => ("(", ")"),
+            Class::Macro | Class::Orphan => ("))", "(("),
+            Class::OtherKind | Class::NoVisibility => (")", "("),
+        }
+    }
+
+    /// PlantUML element keyword for items and stereotype for packages.
+    fn plantuml_shape(self) -> (&'static str, &'static str) {
+        match self {
+            Class::Crate => ("node", "Node"),
+            Class::Mod => ("folder", "Folder"),
+            Class::Type | Class::Public => ("rectangle", "Rectangle"),
+            Class::Enum | Class::Restricted => ("hexagon", "Frame"),
+            Class::Trait => ("interface", "Frame"),
+            Class::Fn | Class::CrateVisible => ("usecase", "Folder"),
+            Class::Value => ("storage", "Database"),
+            Class::Macro => ("component", "Node"),
+            Class::Private => ("card", "Rectangle"),
+            Class::Orphan => ("file", "Cloud"),
+            Class::OtherKind | Class::NoVisibility => ("label", "Rectangle"),
+        }
+    }
+}
+
+/// Every class of a [`StyleBy`], used to emit class definitions.
+fn classes(by: StyleBy) -> &'static [Class] {
+    match by {
+        StyleBy::Kind => &[
+            Class::Crate,
+            Class::Mod,
+            Class::Type,
+            Class::Enum,
+            Class::Trait,
+            Class::Fn,
+            Class::Value,
+            Class::Macro,
+            Class::OtherKind,
+        ],
+        StyleBy::Visibility => &[
+            Class::Public,
+            Class::CrateVisible,
+            Class::Restricted,
+            Class::Private,
+            Class::Orphan,
+            Class::NoVisibility,
+        ],
+    }
+}
+
+/// Returns the first label line of a node, e.g. `mod cli`.
+fn title(node: &Node) -> String {
+    format!("{} {}", node.kind, node.name)
+}
+
+/// Returns the second label line of a node: its visibility or the orphan marker, and
+/// the number of hidden items of a pruned node.
+fn subtitle(node: &Node) -> Option<String> {
+    let status = match &node.visibility {
+        _ if node.orphan => Some("orphan".to_string()),
+        Some(visibility) => Some(visibility.to_string()),
+        None => None,
+    };
+    match (status, node.collapsed) {
+        (status, 0) => status,
+        (Some(status), count) => Some(format!("{} {}", status, render::placeholder_text(count))),
+        (None, count) => Some(render::placeholder_text(count)),
+    }
+}
+
+/// Calls `visit` for every node in pre-order with its id and its parent's id.
+///
+/// # Details
+/// - Ids are `n0`, `n1`, … in pre-order, `n0` being the crate root.
+fn walk<'a>(tree: &'a ModuleTree, mut visit: impl FnMut(&'a Node, usize, Option<usize>)) {
+    let mut next_id = 0;
+    let mut stack = vec![(&tree.root, None)];
+    while let Some((node, parent)) = stack.pop() {
+        let id = next_id;
+        next_id += 1;
+        visit(node, id, parent);
+        for child in node.children.iter().rev() {
+            stack.push((child, Some(id)));
+        }
+    }
+}
+
+/// Renders the module tree as a Graphviz DOT digraph.
+pub fn to_dot(tree: &ModuleTree, options: &DiagramOptions) -> String {
+    let mut dot = String::new();
+    dot.push_str("digraph modules {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, fontname=\"Helvetica\"];\n");
+
+    walk(tree, |node, id, parent| {
+        let mut label = escape_dot(&title(node));
+        if let Some(subtitle) = subtitle(node) {
+            label.push_str("\\n");
+            label.push_str(&escape_dot(&subtitle));
+        }
+
+        let mut attributes = format!("label=\"{}\"", label);
+        if let Some(by) = options.shape_by {
+            write!(attributes, ", shape={}", Class::of(node, by).dot_shape()).unwrap();
+        }
+        if let Some(by) = options.color_by {
+            let color = Class::of(node, by).color();
+            write!(attributes, ", style=filled, fillcolor=\"{}\"", color).unwrap();
+        }
+        writeln!(dot, "    n{} [{}];", id, attributes).unwrap();
+        if let Some(parent) = parent {
+            writeln!(dot, "    n{} -> n{};", parent, id).unwrap();
+        }
+    });
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the module tree as a Mermaid flowchart (`graph`).
+pub fn to_mermaid_graph(tree: &ModuleTree, options: &DiagramOptions) -> String {
+    let mut mermaid = String::from("graph LR\n");
+
+    walk(tree, |node, id, parent| {
+        let mut label = escape_mermaid(&title(node));
+        if let Some(subtitle) = subtitle(node) {
+            label.push_str("<br/>");
+            label.push_str(&escape_mermaid(&subtitle));
+        }
+
+        let (open, close) = match options.shape_by {
+            Some(by) => Class::of(node, by).mermaid_shape(),
+            None => ("[", "]"),
+        };
+        writeln!(mermaid, "    n{}{}\"{}\"{}", id, open, label, close).unwrap();
+        if let Some(parent) = parent {
+            writeln!(mermaid, "    n{} --> n{}", parent, id).unwrap();
+        }
+        if let Some(by) = options.color_by {
+            writeln!(mermaid, "    class n{} {}", id, Class::of(node, by).name()).unwrap();
+        }
+    });
+
+    if let Some(by) = options.color_by {
+        for class in classes(by) {
+            writeln!(
+                mermaid,
+                "    classDef {} fill:{},stroke:#333",
+                class.name(),
+                class.color()
+            )
+            .unwrap();
+        }
+    }
+    mermaid
+}
+
+/// Renders the module tree as a Mermaid mindmap.
+///
+/// # Details
+/// - Mermaid mindmaps do not support `classDef`, so with `color_by` every node is
+///   tagged with a `:::` class such as `:::mod` or `:::pub-crate`. The colors of those
+///   classes are listed in a comment and must be provided by the page's CSS.
+pub fn to_mermaid_mindmap(tree: &ModuleTree, options: &DiagramOptions) -> String {
+    let mut mermaid = String::from("mindmap\n");
+    if let Some(by) = options.color_by {
+        for class in classes(by) {
+            writeln!(
+                mermaid,
+                "%% .{} {{ fill: {} }}",
+                class.name(),
+                class.color()
+            )
+            .unwrap();
+        }
+    }
+
+    let mut depths: Vec<usize> = Vec::new();
+    walk(tree, |node, id, parent| {
+        let depth = parent.map_or(0, |parent| depths[parent] + 1);
+        depths.push(depth);
+
+        let mut label = escape_mermaid(&title(node));
+        if let Some(subtitle) = subtitle(node) {
+            label.push_str(": ");
+            label.push_str(&escape_mermaid(&subtitle));
+        }
+        let (open, close) = match options.shape_by {
+            Some(by) => Class::of(node, by).mindmap_shape(),
+            None => ("[", "]"),
+        };
+        let indent = "  ".repeat(depth + 1);
+        writeln!(mermaid, "{}n{}{}\"{}\"{}", indent, id, open, label, close).unwrap();
+        if let Some(by) = options.color_by {
+            writeln!(mermaid, "{}:::{}", indent, Class::of(node, by).name()).unwrap();
+        }
+    });
+    mermaid
+}
+
+/// Renders the module tree as a PlantUML package diagram.
+///
+/// # Details
+/// - Nodes with children become nested packages, all other nodes become elements
+///   inside their parent package.
+pub fn to_plantuml(tree: &ModuleTree, options: &DiagramOptions) -> String {
+    let mut plantuml = String::from("@startuml\n");
+    let mut next_id = 0;
+    render_plantuml_node(&tree.root, 0, &mut next_id, options, &mut plantuml);
+    plantuml.push_str("@enduml\n");
+    plantuml
+}
+
+fn render_plantuml_node(
+    node: &Node,
+    depth: usize,
+    next_id: &mut usize,
+    options: &DiagramOptions,
+    plantuml: &mut String,
+) {
+    let id = *next_id;
+    *next_id += 1;
+
+    let indent = "  ".repeat(depth);
+    let mut label = escape_plantuml(&title(node));
+    if let Some(subtitle) = subtitle(node) {
+        label.push_str("\\n");
+        label.push_str(&escape_plantuml(&subtitle));
+    }
+    let color = options
+        .color_by
+        .map(|by| format!(" {}", Class::of(node, by).color()))
+        .unwrap_or_default();
+    let shape = options
+        .shape_by
+        .map(|by| Class::of(node, by).plantuml_shape());
+
+    let is_package = !node.children.is_empty() || matches!(node.kind, ItemKind::Crate);
+    if is_package {
+        let stereotype = shape
+            .map(|(_, stereotype)| format!(" <<{}>>", stereotype))
+            .unwrap_or_default();
+        writeln!(
+            plantuml,
+            "{}package \"{}\" as n{}{}{} {{",
+            indent, label, id, stereotype, color
+        )
+        .unwrap();
+        for child in &node.children {
+            render_plantuml_node(child, depth + 1, next_id, options, plantuml);
+        }
+        writeln!(plantuml, "{}}}", indent).unwrap();
+    } else {
+        let element = shape.map_or("rectangle", |(element, _)| element);
+        writeln!(
+            plantuml,
+            "{}{} \"{}\" as n{}{}",
+            indent, element, label, id, color
+        )
+        .unwrap();
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes a label part for Mermaid, which renders labels as HTML, so that generics
+/// such as `Wrapper<T>` are not read as tags.
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn escape_plantuml(text: &str) -> String {
+    text.replace('"', "'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A crate with a restricted visibility, a generic name and a quote in a name.
+    fn tree() -> ModuleTree {
+        let mut wrapper = Node::new(ItemKind::Struct, "Wrapper<T>");
+        wrapper.visibility = Some(Visibility::Restricted("crate::cli".to_string()));
+        let mut quoted = Node::new(ItemKind::Fn, "say\"hi\"");
+        quoted.visibility = Some(Visibility::Private);
+        let mut cli = Node::new(ItemKind::Mod, "cli");
+        cli.visibility = Some(Visibility::Crate);
+        cli.children = vec![wrapper, quoted];
+        cli.collapsed = 2;
+        let mut root = Node::new(ItemKind::Crate, "demo");
+        root.children = vec![cli];
+        ModuleTree { root }
+    }
+
+    const STYLED: DiagramOptions = DiagramOptions {
+        color_by: Some(StyleBy::Kind),
+        shape_by: Some(StyleBy::Visibility),
+    };
+
+    #[test]
+    fn dot() {
+        assert_eq!(
+            to_dot(&tree(), &DiagramOptions::default()),
+            "digraph modules {
+    rankdir=LR;
+    node [shape=box, fontname=\"Helvetica\"];
+    n0 [label=\"crate demo\"];
+    n1 [label=\"mod cli\\npub(crate) … (2 items)\"];
+    n0 -> n1;
+    n2 [label=\"struct Wrapper<T>\\npub(in crate::cli)\"];
+    n1 -> n2;
+    n3 [label=\"fn say\\\"hi\\\"\\npub(self)\"];
+    n1 -> n3;
+}
+"
+        );
+        let styled = to_dot(&tree(), &STYLED);
+        assert!(styled.contains(
+            "n1 [label=\"mod cli\\npub(crate) … (2 items)\", shape=ellipse, style=filled, fillcolor=\"#f28e2b\"];"
+        ));
+    }
+
+    #[test]
+    fn mermaid_graph() {
+        assert_eq!(
+            to_mermaid_graph(&tree(), &DiagramOptions::default()),
+            "graph LR
+    n0[\"crate demo\"]
+    n1[\"mod cli<br/>pub(crate) … (2 items)\"]
+    n0 --> n1
+    n2[\"struct Wrapper#lt;T#gt;<br/>pub(in crate::cli)\"]
+    n1 --> n2
+    n3[\"fn say#quot;hi#quot;<br/>pub(self)\"]
+    n1 --> n3
+"
+        );
+        let styled = to_mermaid_graph(&tree(), &STYLED);
+        assert!(styled.contains("    n3((\"fn say#quot;hi#quot;<br/>pub(self)\"))\n"));
+        assert!(styled.contains("    class n3 fn\n"));
+        assert!(styled.ends_with("    classDef other fill:#bab0ac,stroke:#333\n"));
+    }
+
+    #[test]
+    fn mermaid_mindmap() {
+        assert_eq!(
+            to_mermaid_mindmap(&tree(), &DiagramOptions::default()),
+            "mindmap
+  n0[\"crate demo\"]
+    n1[\"mod cli: pub(crate) … (2 items)\"]
+      n2[\"struct Wrapper#lt;T#gt;: pub(in crate::cli)\"]
+      n3[\"fn say#quot;hi#quot;: pub(self)\"]
+"
+        );
+        let styled = to_mermaid_mindmap(&tree(), &STYLED);
+        assert!(styled.starts_with("mindmap\n%% .crate { fill: #4e79a7 }\n"));
+        assert!(styled.contains(
+            "      n2{{\"struct Wrapper#lt;T#gt;: pub(in crate::cli)\"}}\n      :::type\n"
+        ));
+    }
+
+    #[test]
+    fn plantuml() {
+        assert_eq!(
+            to_plantuml(&tree(), &DiagramOptions::default()),
+            "@startuml
+package \"crate demo\" as n0 {
+  package \"mod cli\\npub(crate) … (2 items)\" as n1 {
+    rectangle \"struct Wrapper<T>\\npub(in crate::cli)\" as n2
+    rectangle \"fn say'hi'\\npub(self)\" as n3
+  }
+}
+@enduml
+"
+        );
+        let styled = to_plantuml(&tree(), &STYLED);
+        assert!(styled
+            .contains("    hexagon \"struct Wrapper<T>\\npub(in crate::cli)\" as n2 #59a14f\n"));
+        assert!(styled.contains(
+            "  package \"mod cli\\npub(crate) … (2 items)\" as n1 <<Folder>> #f28e2b {\n"
+        ));
+    }
+}
@@ -0,0 +1,588 @@
+//! Structural diff between two module trees.
+//!
+//! Items are matched by kind and path. An item that disappears from one path and
+//! appears under the same name and kind at exactly one other path is reported as
+//! moved; the items below a moved module follow it and are compared at their new path.
+
+use crate::render;
+use crate::tree::{ItemKind, ModuleTree, Node, Visibility};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::io;
+
+/// The identifier of the JSON diff schema.
+pub const SCHEMA_NAME: &str = "module-structure-cleaner/module-diff";
+
+/// The version of the JSON diff schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// An item that exists in only one of the two trees.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Item {
+    /// The full path of the item, e.g. `crate::a::Item`.
+    pub path: String,
+    /// The kind of the item.
+    #[serde(serialize_with = "serialize_display")]
+    pub kind: ItemKind,
+    /// The visibility of the item.
+    #[serde(serialize_with = "serialize_optional_display")]
+    pub visibility: Option<Visibility>,
+}
+
+/// An item that moved to another path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Move {
+    /// The path in the old tree.
+    pub from: String,
+    /// The path in the new tree.
+    pub to: String,
+    /// The kind of the item.
+    #[serde(serialize_with = "serialize_display")]
+    pub kind: ItemKind,
+}
+
+/// An item whose visibility changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VisibilityChange {
+    /// The path of the item in the new tree.
+    pub path: String,
+    /// The kind of the item.
+    #[serde(serialize_with = "serialize_display")]
+    pub kind: ItemKind,
+    /// The visibility in the old tree.
+    #[serde(serialize_with = "serialize_optional_display")]
+    pub old: Option<Visibility>,
+    /// The visibility in the new tree.
+    #[serde(serialize_with = "serialize_optional_display")]
+    pub new: Option<Visibility>,
+}
+
+/// The change status of a node in the annotated tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Unchanged,
+    Added,
+    Removed,
+    Moved {
+        from: String,
+    },
+    VisibilityChanged {
+        old: Option<Visibility>,
+    },
+    MovedAndVisibilityChanged {
+        from: String,
+        old: Option<Visibility>,
+    },
+}
+
+/// A node of the annotated tree: the new tree with removed items merged back in.
+#[derive(Debug, Clone)]
+struct DiffNode {
+    name: String,
+    text: String,
+    status: Status,
+    children: Vec<DiffNode>,
+}
+
+/// The differences between two module trees.
+#[derive(Debug, Clone)]
+pub struct TreeDiff {
+    /// Items that only exist in the new tree, in pre-order.
+    pub added: Vec<Item>,
+    /// Items that only exist in the old tree, in pre-order.
+    pub removed: Vec<Item>,
+    /// Items that moved to another path. Items below a moved module are not listed.
+    pub moved: Vec<Move>,
+    /// Items whose visibility changed.
+    pub visibility_changed: Vec<VisibilityChange>,
+    annotated: DiffNode,
+}
+
+/// Key identifying an item: modules and functions may share a path.
+type Key = (String, ItemKind);
+
+impl TreeDiff {
+    /// Compares two module trees.
+    ///
+    /// # Parameters
+    /// - `old`: The tree before the change.
+    /// - `new`: The tree after the change.
+    pub fn new(old: &ModuleTree, new: &ModuleTree) -> Self {
+        let old_nodes = old.nodes();
+        let new_nodes = new.nodes();
+        let new_items: HashMap<Key, &Node> = new_nodes
+            .iter()
+            .map(|(path, _, node)| ((path.clone(), node.kind.clone()), *node))
+            .collect();
+        let old_keys: BTreeSet<Key> = old_nodes
+            .iter()
+            .map(|(path, _, node)| (path.clone(), node.kind.clone()))
+            .collect();
+
+        // New items without an old counterpart at the same path can be move targets
+        let mut move_targets: HashMap<(String, ItemKind), Vec<String>> = HashMap::new();
+        for (path, _, node) in &new_nodes {
+            if !old_keys.contains(&(path.clone(), node.kind.clone())) {
+                move_targets
+                    .entry((node.name.clone(), node.kind.clone()))
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+
+        // A target reached through a moved parent beats a move by name, but the parent
+        // may be visited after the item that took the target; match again without it
+        let mut reserved = BTreeSet::new();
+        let matching = loop {
+            let matching = match_nodes(&old_nodes, &new_items, move_targets.clone(), &reserved);
+            if matching.claimed.is_subset(&reserved) {
+                break matching;
+            }
+            reserved.extend(matching.claimed.iter().cloned());
+        };
+        let Matching {
+            matched,
+            moved,
+            removed,
+            removed_nodes,
+            ..
+        } = matching;
+        let mut diff = TreeDiff {
+            added: Vec::new(),
+            removed,
+            moved,
+            visibility_changed: Vec::new(),
+            annotated: DiffNode {
+                name: String::new(),
+                text: String::new(),
+                status: Status::Unchanged,
+                children: Vec::new(),
+            },
+        };
+
+        // Annotate the new tree and collect additions and visibility changes
+        let moves: HashMap<&str, &str> = diff
+            .moved
+            .iter()
+            .map(|moved| (moved.to.as_str(), moved.from.as_str()))
+            .collect();
+        diff.annotated = annotate(&new.root, "crate", &mut |path, node| {
+            let key = (path.to_string(), node.kind.clone());
+            let Some((_, old_node)) = matched.get(&key) else {
+                if path != "crate" {
+                    diff.added.push(Item {
+                        path: path.to_string(),
+                        kind: node.kind.clone(),
+                        visibility: node.visibility.clone(),
+                    });
+                    return Status::Added;
+                }
+                return Status::Unchanged;
+            };
+
+            let moved_from = moves.get(path).map(|from| from.to_string());
+            let visibility_changed = old_node.visibility != node.visibility;
+            if visibility_changed {
+                diff.visibility_changed.push(VisibilityChange {
+                    path: path.to_string(),
+                    kind: node.kind.clone(),
+                    old: old_node.visibility.clone(),
+                    new: node.visibility.clone(),
+                });
+            }
+            let old = old_node.visibility.clone();
+            match (moved_from, visibility_changed) {
+                (Some(from), true) => Status::MovedAndVisibilityChanged { from, old },
+                (Some(from), false) => Status::Moved { from },
+                (None, true) => Status::VisibilityChanged { old },
+                (None, false) => Status::Unchanged,
+            }
+        });
+
+        // Merge removed items back in below their (possibly moved) parent
+        for (mapped, node) in removed_nodes {
+            let parent = mapped
+                .rsplit_once("::")
+                .map_or("crate", |(parent, _)| parent);
+            let removed = DiffNode {
+                name: node.name.clone(),
+                text: render::item_text(node),
+                status: Status::Removed,
+                children: Vec::new(),
+            };
+            match find_mut(&mut diff.annotated, "crate", parent) {
+                Some(parent) => parent.children.push(removed),
+                None => diff.annotated.children.push(removed),
+            }
+        }
+        diff
+    }
+
+    /// Returns `true` if the two trees are structurally identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.visibility_changed.is_empty()
+    }
+
+    /// Renders the new tree annotated with the changes.
+    ///
+    /// # Details
+    /// - Every line starts with a marker: `+` added, `-` removed, `>` moved,
+    ///   `~` visibility changed, or a space for unchanged items.
+    /// - Removed items are shown below their old parent, at its new path if it moved.
+    /// - Connectors are Unicode box-drawing characters, ready to be cleaned to ASCII.
+    pub fn to_annotated_tree(&self) -> String {
+        let mut text = String::new();
+        render::draw(
+            &self.annotated,
+            |node| &node.children,
+            |node, prefix| {
+                let (marker, note) = match &node.status {
+                    Status::Unchanged => (' ', String::new()),
+                    Status::Added => ('+', String::new()),
+                    Status::Removed => ('-', String::new()),
+                    Status::Moved { from } => ('>', format!(" (moved from {})", from)),
+                    Status::VisibilityChanged { old } => {
+                        ('~', format!(" (was {})", visibility_text(old)))
+                    }
+                    Status::MovedAndVisibilityChanged { from, old } => (
+                        '>',
+                        format!(" (moved from {}, was {})", from, visibility_text(old)),
+                    ),
+                };
+                writeln!(text, "{} {}{}{}", marker, prefix, node.text, note).unwrap();
+            },
+        );
+        text
+    }
+
+    /// Renders the changes as a unified-style text diff.
+    ///
+    /// # Parameters
+    /// - `old_label`: The name of the old snapshot shown in the `---` header.
+    /// - `new_label`: The name of the new snapshot shown in the `+++` header.
+    ///
+    /// # Details
+    /// - Each section starts with an `@@` header. Removed items are `-` lines, added
+    ///   items `+` lines, and moves and visibility changes are `-`/`+` line pairs.
+    pub fn to_unified(&self, old_label: &str, new_label: &str) -> String {
+        let mut text = format!("--- {}\n+++ {}\n", old_label, new_label);
+        let line = |kind: &ItemKind, path: &str, visibility: &Option<Visibility>| match visibility {
+            Some(visibility) => format!("{} {}: {}", kind, path, visibility),
+            None => format!("{} {}", kind, path),
+        };
+
+        if !self.removed.is_empty() {
+            writeln!(text, "@@ removed: {} @@", self.removed.len()).unwrap();
+            for item in &self.removed {
+                writeln!(text, "-{}", line(&item.kind, &item.path, &item.visibility)).unwrap();
+            }
+        }
+        if !self.added.is_empty() {
+            writeln!(text, "@@ added: {} @@", self.added.len()).unwrap();
+            for item in &self.added {
+                writeln!(text, "+{}", line(&item.kind, &item.path, &item.visibility)).unwrap();
+            }
+        }
+        if !self.moved.is_empty() {
+            writeln!(text, "@@ moved: {} @@", self.moved.len()).unwrap();
+            for moved in &self.moved {
+                writeln!(text, "-{} {}", moved.kind, moved.from).unwrap();
+                writeln!(text, "+{} {}", moved.kind, moved.to).unwrap();
+            }
+        }
+        if !self.visibility_changed.is_empty() {
+            writeln!(
+                text,
+                "@@ visibility changed: {} @@",
+                self.visibility_changed.len()
+            )
+            .unwrap();
+            for change in &self.visibility_changed {
+                writeln!(text, "-{}", line(&change.kind, &change.path, &change.old)).unwrap();
+                writeln!(text, "+{}", line(&change.kind, &change.path, &change.new)).unwrap();
+            }
+        }
+        text
+    }
+
+    /// Renders the changes as a pretty-printed JSON document.
+    ///
+    /// # Details
+    /// - The document has the keys `schema`, `version`, `added`, `removed`, `moved`
+    ///   and `visibility_changed`.
+    pub fn to_json(&self) -> io::Result<String> {
+        #[derive(Serialize)]
+        struct Document<'a> {
+            schema: &'static str,
+            version: u32,
+            added: &'a [Item],
+            removed: &'a [Item],
+            moved: &'a [Move],
+            visibility_changed: &'a [VisibilityChange],
+        }
+
+        let mut json = serde_json::to_string_pretty(&Document {
+            schema: SCHEMA_NAME,
+            version: SCHEMA_VERSION,
+            added: &self.added,
+            removed: &self.removed,
+            moved: &self.moved,
+            visibility_changed: &self.visibility_changed,
+        })?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// The outcome of matching the nodes of the old tree against the new tree.
+struct Matching<'a> {
+    /// New items with their old path and node.
+    matched: BTreeMap<Key, (String, &'a Node)>,
+    moved: Vec<Move>,
+    removed: Vec<Item>,
+    /// Removed nodes with their path mapped through moved modules.
+    removed_nodes: Vec<(String, &'a Node)>,
+    /// New items matched below a moved module.
+    claimed: BTreeSet<Key>,
+}
+
+/// Matches every old node to a new item at its path, possibly below a moved module,
+/// or to a move target of the same name and kind.
+///
+/// # Parameters
+/// - `old_nodes`: The nodes of the old tree, in pre-order.
+/// - `new_items`: The items of the new tree by path and kind.
+/// - `move_targets`: New items without an old counterpart, by name and kind.
+/// - `reserved`: Targets that a move by name must not take.
+fn match_nodes<'a>(
+    old_nodes: &[(String, usize, &'a Node)],
+    new_items: &HashMap<Key, &Node>,
+    mut move_targets: HashMap<(String, ItemKind), Vec<String>>,
+    reserved: &BTreeSet<Key>,
+) -> Matching<'a> {
+    let mut moved_prefixes: Vec<(String, String)> = Vec::new();
+    let mut matching = Matching {
+        matched: BTreeMap::new(),
+        moved: Vec::new(),
+        removed: Vec::new(),
+        removed_nodes: Vec::new(),
+        claimed: BTreeSet::new(),
+    };
+
+    // Pre-order: a moved module is matched before the items below it
+    for (old_path, _, old_node) in old_nodes.iter().skip(1) {
+        let kind = old_node.kind.clone();
+        let mapped = map_path(old_path, &moved_prefixes);
+
+        let name_key = (old_node.name.clone(), kind.clone());
+        let mapped_key = (mapped.clone(), kind.clone());
+        if new_items.contains_key(&mapped_key) {
+            // An item matched below a moved module is no longer free to be a move target
+            if let Some(targets) = move_targets.get_mut(&name_key) {
+                targets.retain(|target| *target != mapped);
+            }
+            if mapped != *old_path {
+                matching.claimed.insert(mapped_key.clone());
+            }
+            matching
+                .matched
+                .insert(mapped_key, (old_path.clone(), *old_node));
+            continue;
+        }
+
+        // Targets matched through a moved parent were taken in the meantime
+        let candidates = move_targets.get_mut(&name_key).and_then(|targets| {
+            targets.retain(|target| {
+                let key = (target.clone(), kind.clone());
+                !matching.matched.contains_key(&key) && !reserved.contains(&key)
+            });
+            (targets.len() == 1).then_some(targets)
+        });
+        match candidates {
+            Some(targets) => {
+                let target = targets.pop().unwrap();
+                moved_prefixes.push((old_path.clone(), target.clone()));
+                matching.moved.push(Move {
+                    from: old_path.clone(),
+                    to: target.clone(),
+                    kind: kind.clone(),
+                });
+                matching
+                    .matched
+                    .insert((target, kind), (old_path.clone(), *old_node));
+            }
+            None => {
+                matching.removed.push(Item {
+                    path: old_path.clone(),
+                    kind,
+                    visibility: old_node.visibility.clone(),
+                });
+                matching.removed_nodes.push((mapped, *old_node));
+            }
+        }
+    }
+    matching
+}
+
+/// Rewrites an old path to its new location when one of its ancestors moved.
+fn map_path(path: &str, moved_prefixes: &[(String, String)]) -> String {
+    for (from, to) in moved_prefixes.iter().rev() {
+        if path == from {
+            return to.clone();
+        }
+        if let Some(rest) = path.strip_prefix(from.as_str()) {
+            if rest.starts_with("::") {
+                return format!("{}{}", to, rest);
+            }
+        }
+    }
+    path.to_string()
+}
+
+/// Builds the annotated copy of a new tree, asking `status` for every node.
+fn annotate(node: &Node, path: &str, status: &mut impl FnMut(&str, &Node) -> Status) -> DiffNode {
+    DiffNode {
+        name: node.name.clone(),
+        text: render::item_text(node),
+        status: status(path, node),
+        children: node
+            .children
+            .iter()
+            .map(|child| annotate(child, &Node::child_path(path, child), status))
+            .collect(),
+    }
+}
+
+/// Finds the annotated node at `target`, given the path of `node`.
+fn find_mut<'a>(node: &'a mut DiffNode, path: &str, target: &str) -> Option<&'a mut DiffNode> {
+    if path == target {
+        return Some(node);
+    }
+    if !target.starts_with(path) {
+        return None;
+    }
+    node.children.iter_mut().find_map(|child| {
+        let child_path = format!("{}::{}", path, child.name);
+        find_mut(child, &child_path, target)
+    })
+}
+
+fn visibility_text(visibility: &Option<Visibility>) -> String {
+    visibility
+        .as_ref()
+        .map_or_else(|| "none".to_string(), ToString::to_string)
+}
+
+fn serialize_display<S: serde::Serializer>(
+    value: &impl std::fmt::Display,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn serialize_optional_display<S: serde::Serializer>(
+    value: &Option<impl std::fmt::Display>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(text: &str) -> ModuleTree {
+        ModuleTree::parse(text).unwrap()
+    }
+
+    fn paths(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|item| item.path.as_str()).collect()
+    }
+
+    #[test]
+    fn item_below_moved_module_is_not_a_second_move_target() {
+        let old = tree(
+            "crate c\n\
+             ├── mod p\n\
+             │   └── mod m\n\
+             │       └── fn f\n\
+             └── mod q\n    \
+                 └── fn f\n",
+        );
+        let new = tree(
+            "crate c\n\
+             └── mod r\n    \
+                 └── mod m\n        \
+                     └── fn f\n",
+        );
+        let diff = TreeDiff::new(&old, &new);
+
+        assert_eq!(
+            diff.moved,
+            vec![Move {
+                from: "crate::p::m".to_string(),
+                to: "crate::r::m".to_string(),
+                kind: ItemKind::Mod,
+            }]
+        );
+        assert_eq!(
+            paths(&diff.removed),
+            ["crate::p", "crate::q", "crate::q::f"]
+        );
+        assert_eq!(paths(&diff.added), ["crate::r"]);
+    }
+
+    #[test]
+    fn item_is_not_moved_onto_a_target_of_a_later_moved_module() {
+        let old = tree(
+            "crate c\n\
+             ├── mod a\n\
+             │   └── fn f\n\
+             └── mod p\n    \
+                 └── mod m\n        \
+                     └── fn f\n",
+        );
+        let new = tree(
+            "crate c\n\
+             └── mod r\n    \
+                 └── mod m\n        \
+                     └── fn f\n",
+        );
+        let diff = TreeDiff::new(&old, &new);
+
+        assert_eq!(
+            diff.moved,
+            vec![Move {
+                from: "crate::p::m".to_string(),
+                to: "crate::r::m".to_string(),
+                kind: ItemKind::Mod,
+            }]
+        );
+        assert_eq!(
+            paths(&diff.removed),
+            ["crate::a", "crate::a::f", "crate::p"]
+        );
+        assert_eq!(paths(&diff.added), ["crate::r"]);
+        assert!(!diff.to_annotated_tree().contains("moved from crate::a::f"));
+    }
+
+    #[test]
+    fn unique_name_is_moved() {
+        let old = tree("crate c\n├── mod a\n│   └── fn f\n└── mod b\n");
+        let new = tree("crate c\n├── mod a\n└── mod b\n    └── fn f\n");
+        let diff = TreeDiff::new(&old, &new);
+
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!(diff.moved[0].from, "crate::a::f");
+        assert_eq!(diff.moved[0].to, "crate::b::f");
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+//! Detection of the input encoding and transcoding to and from UTF-8.
+//!
+//! Cleaning works on UTF-8. Input in another encoding, such as the UTF-16LE that
+//! PowerShell redirects to files, is decoded while it is read, and output can be
+//! encoded again while it is written. Encodings are the [`encoding_rs`] statics,
+//! e.g. [`encoding_rs::UTF_16LE`].
+
+use crate::ansi;
+use encoding_rs::{CoderResult, EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// Size of the buffers used by [`DecodeReader`].
+const BUFFER_LEN: usize = 8192;
+
+/// The UTF-8 encoding of U+FEFF.
+const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Looks up an encoding by a label such as `utf-16le`, `latin1` or `windows-1252`.
+///
+/// # Returns
+/// - `None` if the label is unknown.
+///
+/// # Details
+/// - Labels follow the WHATWG Encoding Standard, so `utf-16` means UTF-16LE.
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/// Guesses the encoding of text from its first bytes.
+///
+/// # Parameters
+/// - `sample`: The beginning of the input; a few kilobytes are enough.
+///
+/// # Returns
+/// - The encoding of a byte order mark, if the sample starts with one.
+/// - UTF-16LE or UTF-16BE if every other byte is NUL, as in mostly ASCII UTF-16 text.
+/// - Windows-1252 if the sample is not valid UTF-8, contains a byte 0x80 to 0x9F, such
+///   as the curly quote 0x93, and has neither multi-byte UTF-8 characters nor complete
+///   raw 8-bit CSI or OSC sequences.
+/// - UTF-8 otherwise, including other input that is not valid UTF-8.
+///
+/// # Details
+/// - Invalid UTF-8 that is not recognized as Windows-1252, e.g. ASCII with a stray
+///   0xFF, is left to [`crate::Cleaner`], which reports it or replaces it if it is
+///   lossy. `--input-encoding` selects any other code page explicitly.
+pub fn detect(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    if let Some(encoding) = detect_utf16(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        Err(error) if error.error_len().is_none() => UTF_8,
+        Err(_) if looks_like_windows_1252(sample) => WINDOWS_1252,
+        Err(_) => UTF_8,
+    }
+}
+
+/// Returns `true` if the encoding is UTF-16LE or UTF-16BE.
+pub fn is_utf16(encoding: &'static Encoding) -> bool {
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Returns `true` if the input starts with a UTF-8 byte order mark.
+///
+/// # Details
+/// - Only the bytes in the reader's buffer are inspected; none are consumed. Call it
+///   before [`decoding_reader`], which strips the mark.
+pub fn starts_with_utf8_bom(reader: &mut impl BufRead) -> io::Result<bool> {
+    Ok(reader.fill_buf()?.starts_with(UTF_8_BOM))
+}
+
+/// Wraps a reader so that it yields UTF-8.
+///
+/// # Parameters
+/// - `reader`: The source of the encoded text.
+/// - `encoding`: The encoding of the input, or `None` to [`detect`] it from the
+///   bytes available in the reader's buffer.
+///
+/// # Returns
+/// - `Ok(Box<dyn BufRead>)` reading UTF-8 without a byte order mark.
+/// - `Err(io::Error)` if the first read fails.
+///
+/// # Details
+/// - UTF-8 input is passed through unchanged after its byte order mark, so invalid
+///   bytes still surface as errors where the text is read.
+/// - Bytes that are malformed in any other encoding are decoded as U+FFFD.
+pub fn decoding_reader<'a>(
+    mut reader: impl BufRead + 'a,
+    encoding: Option<&'static Encoding>,
+) -> io::Result<Box<dyn BufRead + 'a>> {
+    let sample = reader.fill_buf()?;
+    let encoding = encoding.unwrap_or_else(|| detect(sample));
+    if encoding == UTF_8 {
+        if sample.starts_with(UTF_8_BOM) {
+            reader.consume(3);
+        }
+        return Ok(Box::new(reader));
+    }
+    Ok(Box::new(BufReader::new(DecodeReader::new(
+        reader, encoding,
+    ))))
+}
+
+/// Wraps a writer so that UTF-8 written to it is stored in `encoding`.
+///
+/// # Parameters
+/// - `writer`: The destination of the encoded text.
+/// - `encoding`: The encoding of the output.
+/// - `utf8_bom`: Whether UTF-8 output starts with a byte order mark, e.g. because the
+///   input did, see [`starts_with_utf8_bom`]. Ignored for other encodings.
+///
+/// # Details
+/// - UTF-8 output is otherwise passed through unchanged.
+/// - UTF-16 output always starts with a byte order mark.
+/// - The byte order mark is written with the first text, so empty output stays empty.
+/// - Characters that `encoding` cannot represent are written as `?`.
+pub fn encoding_writer<'a>(
+    writer: impl Write + 'a,
+    encoding: &'static Encoding,
+    utf8_bom: bool,
+) -> Box<dyn Write + 'a> {
+    if encoding == UTF_8 && !utf8_bom {
+        return Box::new(writer);
+    }
+    Box::new(EncodeWriter {
+        inner: writer,
+        encoding,
+        encoder: encoding.new_encoder(),
+        pending: Vec::new(),
+        bom_written: encoding != UTF_8 && !is_utf16(encoding),
+    })
+}
+
+/// Returns `true` if invalid UTF-8 looks like Windows-1252 text.
+///
+/// # Details
+/// - The sample must contain a byte 0x80 to 0x9F, where Windows-1252 keeps its curly
+///   quotes, dashes and the euro sign. Such bytes never start a UTF-8 character and
+///   are C1 controls in Latin-1, so they are the one clear sign of Windows-1252.
+/// - Samples holding a valid multi-byte UTF-8 character are UTF-8 with a few corrupt
+///   bytes, and samples holding a complete raw 8-bit CSI or OSC are colored output,
+///   so neither is Windows-1252.
+fn looks_like_windows_1252(sample: &[u8]) -> bool {
+    let has_c1_byte = sample.iter().any(|byte| (0x80..=0x9f).contains(byte));
+    let has_multibyte_utf8 = sample.utf8_chunks().any(|chunk| !chunk.valid().is_ascii());
+    let has_raw_sequence =
+        (0..sample.len()).any(|index| ansi::raw_c1_sequence_len(&sample[index..]).is_some());
+    has_c1_byte && !has_multibyte_utf8 && !has_raw_sequence
+}
+
+/// Recognizes UTF-16 without a byte order mark by its NUL bytes.
+fn detect_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let even = sample.iter().step_by(2).filter(|&&byte| byte == 0).count();
+    let odd = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&byte| byte == 0)
+        .count();
+    if odd * 2 > pairs && even * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even * 2 > pairs && odd * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// A reader that decodes its input to UTF-8.
+struct DecodeReader<R> {
+    inner: R,
+    decoder: encoding_rs::Decoder,
+    input: Box<[u8]>,
+    input_range: (usize, usize),
+    output: Vec<u8>,
+    output_pos: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: Read> DecodeReader<R> {
+    fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self {
+            inner,
+            decoder: encoding.new_decoder_with_bom_removal(),
+            input: vec![0; BUFFER_LEN].into_boxed_slice(),
+            input_range: (0, 0),
+            output: Vec::new(),
+            output_pos: 0,
+            eof: false,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.output_pos < self.output.len() {
+                let available = &self.output[self.output_pos..];
+                let len = available.len().min(buf.len());
+                buf[..len].copy_from_slice(&available[..len]);
+                self.output_pos += len;
+                return Ok(len);
+            }
+            if self.done {
+                return Ok(0);
+            }
+
+            let (start, end) = self.input_range;
+            if start == end && !self.eof {
+                let len = self.inner.read(&mut self.input)?;
+                self.input_range = (0, len);
+                self.eof = len == 0;
+            }
+
+            let (start, end) = self.input_range;
+            let capacity = self
+                .decoder
+                .max_utf8_buffer_length(end - start)
+                .unwrap_or(BUFFER_LEN * 3)
+                .max(BUFFER_LEN);
+            self.output.resize(capacity, 0);
+            let (result, read, written, _) =
+                self.decoder
+                    .decode_to_utf8(&self.input[start..end], &mut self.output, self.eof);
+            self.output.truncate(written);
+            self.output_pos = 0;
+            self.input_range.0 += read;
+            self.done = self.eof && result == CoderResult::InputEmpty;
+        }
+    }
+}
+
+/// A writer that encodes the UTF-8 written to it.
+struct EncodeWriter<W> {
+    inner: W,
+    encoding: &'static Encoding,
+    encoder: encoding_rs::Encoder,
+    /// The start of a character that was split across writes.
+    pending: Vec<u8>,
+    bom_written: bool,
+}
+
+impl<W: Write> EncodeWriter<W> {
+    /// Encodes `text` to the inner writer.
+    ///
+    /// # Details
+    /// - encoding_rs only decodes UTF-16, so UTF-16 is encoded here.
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        if is_utf16(self.encoding) {
+            let big_endian = self.encoding == UTF_16BE;
+            let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+            let bom = (!self.bom_written).then_some(0xFEFF);
+            for unit in bom.into_iter().chain(text.encode_utf16()) {
+                match big_endian {
+                    true => bytes.extend_from_slice(&unit.to_be_bytes()),
+                    false => bytes.extend_from_slice(&unit.to_le_bytes()),
+                }
+            }
+            self.bom_written = true;
+            return self.inner.write_all(&bytes);
+        }
+
+        if !self.bom_written {
+            // Only UTF-8 output that asked for a byte order mark gets here
+            self.bom_written = true;
+            self.inner.write_all(UTF_8_BOM)?;
+        }
+        let mut text = text;
+        let mut bytes = vec![0; BUFFER_LEN];
+        loop {
+            let (result, read, written) = self
+                .encoder
+                .encode_from_utf8_without_replacement(text, &mut bytes, false);
+            self.inner.write_all(&bytes[..written])?;
+            text = &text[read..];
+            match result {
+                EncoderResult::InputEmpty => return Ok(()),
+                EncoderResult::OutputFull => {}
+                EncoderResult::Unmappable(_) => self.inner.write_all(b"?")?,
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for EncodeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+        };
+        let pending = std::mem::take(&mut self.pending);
+        let (text, rest) = pending.split_at(valid);
+        self.write_text(std::str::from_utf8(text).expect("validated above"))?;
+        self.pending = rest.to_vec();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_byte_order_marks_and_utf16() {
+        assert_eq!(detect(b"\xEF\xBB\xBFtext"), UTF_8);
+        assert_eq!(detect(b"\xFF\xFEt\x00"), UTF_16LE);
+        assert_eq!(detect(b"t\x00e\x00x\x00t\x00"), UTF_16LE);
+        assert_eq!(detect(b"\x00t\x00e\x00x\x00t"), UTF_16BE);
+    }
+
+    #[test]
+    fn windows_1252_is_recognized_by_its_punctuation() {
+        assert_eq!(detect(b"name \x93quoted\x94 done\n"), WINDOWS_1252);
+        assert_eq!(detect(b"Z\x9Eilina caf\xe9\n"), WINDOWS_1252);
+    }
+
+    #[test]
+    fn other_invalid_utf8_is_left_to_the_cleaner() {
+        assert_eq!(detect(b"hello\nbad\xff\n"), UTF_8);
+        assert_eq!(detect(b"caf\xe9"), UTF_8);
+        assert_eq!(detect(b"plain \x9b31mred\x9b0m \x93\n"), UTF_8);
+        assert_eq!(detect(b"\xe2\x94\x9c\x93\n"), UTF_8);
+        assert_eq!(detect("├── crate".as_bytes()), UTF_8);
+    }
+
+    #[test]
+    fn declared_code_page_is_decoded() {
+        let mut text = String::new();
+        decoding_reader(&b"caf\xe9\n"[..], for_label("windows-1252"))
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "café\n");
+    }
+
+    fn transcode(input: &[u8], output_encoding: &'static Encoding) -> Vec<u8> {
+        let mut input = input;
+        let utf8_bom = starts_with_utf8_bom(&mut input).unwrap();
+        let mut output = Vec::new();
+        let mut writer = encoding_writer(&mut output, output_encoding, utf8_bom);
+        io::copy(&mut decoding_reader(input, None).unwrap(), &mut writer).unwrap();
+        drop(writer);
+        output
+    }
+
+    #[test]
+    fn utf8_byte_order_mark_is_written_back() {
+        let input = "\u{feff}├── crate\n".as_bytes();
+        assert_eq!(transcode(input, UTF_8), input);
+        assert_eq!(
+            transcode("├── crate\n".as_bytes(), UTF_8),
+            "├── crate\n".as_bytes()
+        );
+        assert_eq!(transcode(UTF_8_BOM, UTF_8), b"");
+        assert_eq!(transcode(input, WINDOWS_1252), b"??? crate\n");
+    }
+
+    #[test]
+    fn utf16_output_always_starts_with_a_byte_order_mark() {
+        assert_eq!(transcode(b"ab", UTF_16LE), b"\xFF\xFEa\x00b\x00");
+        assert_eq!(
+            transcode(b"\xEF\xBB\xBFab", UTF_16BE),
+            b"\xFE\xFF\x00a\x00b"
+        );
+        assert_eq!(transcode(b"\xFF\xFEa\x00b\x00", UTF_8), b"ab");
+    }
+}
@@ -0,0 +1,109 @@
+//! JSON and YAML export of the module tree.
+//!
+//! Both formats share one versioned document schema:
+//!
+//! ```json
+//! {
+//!   "schema": "module-structure-cleaner/module-tree",
+//!   "version": 1,
+//!   "root": {
+//!     "name": "my_crate",
+//!     "kind": "crate",
+//!     "visibility": null,
+//!     "path": "crate",
+//!     "children": [
+//!       {
+//!         "name": "tests",
+//!         "kind": "mod",
+//!         "visibility": "pub(self)",
+//!         "path": "crate::tests",
+//!         "attributes": ["#[cfg(test)]"],
+//!         "children": []
+//!       }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! `attributes` is omitted when empty and `orphan` is only present, as `true`, for
+//! orphaned modules. `collapsed` is only present on pruned nodes and counts the items
+//! hidden below them. Fields are only ever added within a schema version; renaming or
+//! removing a field increments [`SCHEMA_VERSION`].
+
+use crate::tree::{ModuleTree, Node};
+use serde::Serialize;
+use std::io;
+
+/// The identifier of the document schema.
+pub const SCHEMA_NAME: &str = "module-structure-cleaner/module-tree";
+
+/// The version of the document schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The top-level exported document.
+#[derive(Debug, Serialize)]
+struct Document {
+    schema: &'static str,
+    version: u32,
+    root: NodeDocument,
+}
+
+/// An exported node with its full path.
+#[derive(Debug, Serialize)]
+struct NodeDocument {
+    name: String,
+    kind: String,
+    visibility: Option<String>,
+    path: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attributes: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    orphan: bool,
+    #[serde(skip_serializing_if = "is_zero")]
+    collapsed: usize,
+    children: Vec<NodeDocument>,
+}
+
+impl NodeDocument {
+    fn new(node: &Node, path: String) -> Self {
+        Self {
+            name: node.name.clone(),
+            kind: node.kind.to_string(),
+            visibility: node.visibility.as_ref().map(ToString::to_string),
+            children: node
+                .children
+                .iter()
+                .map(|child| NodeDocument::new(child, Node::child_path(&path, child)))
+                .collect(),
+            path,
+            attributes: node.attributes.clone(),
+            orphan: node.orphan,
+            collapsed: node.collapsed,
+        }
+    }
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+fn document(tree: &ModuleTree) -> Document {
+    Document {
+        schema: SCHEMA_NAME,
+        version: SCHEMA_VERSION,
+        root: NodeDocument::new(&tree.root, "crate".to_string()),
+    }
+}
+
+/// Exports the module tree as a pretty-printed JSON document.
+pub fn to_json(tree: &ModuleTree) -> io::Result<String> {
+    let mut json = serde_json::to_string_pretty(&document(tree))?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Exports the module tree as a YAML document.
+pub fn to_yaml(tree: &ModuleTree) -> io::Result<String> {
+    serde_yaml::to_string(&document(tree))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
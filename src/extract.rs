@@ -0,0 +1,621 @@
+//! Native module-tree extraction from Rust sources.
+//!
+//! The extractor starts at the crate root file, follows `mod foo;` declarations and
+//! `#[path]` attributes the way rustc resolves them, and parses every file with `syn`.
+//! It needs neither cargo-modules nor a network connection and produces the same
+//! [`ModuleTree`] that is parsed from `cargo modules structure` output.
+
+use crate::cargo_modules::StructureOptions;
+use crate::tree::{ItemKind, ModuleTree, Node, Visibility};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The result of extracting the module tree of a crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extraction {
+    /// The module tree.
+    pub tree: ModuleTree,
+    /// The crate root file.
+    pub root_file: PathBuf,
+    /// Every source file reached through `mod` declarations, the root file included.
+    pub files: Vec<PathBuf>,
+    /// `mod` declarations whose file does not exist.
+    pub missing: Vec<MissingModule>,
+}
+
+/// A `mod foo;` declaration whose file does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingModule {
+    /// The path of the module, e.g. `crate::a::foo`.
+    pub path: String,
+    /// The file containing the declaration.
+    pub declared_in: PathBuf,
+    /// The files that were looked for, e.g. `src/a/foo.rs` and `src/a/foo/mod.rs`.
+    pub candidates: Vec<PathBuf>,
+}
+
+/// The parts of `Cargo.toml` that decide the crate root files.
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+    lib: Option<Target>,
+    #[serde(default)]
+    bin: Vec<Target>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    name: Option<String>,
+    path: Option<PathBuf>,
+}
+
+/// Extracts the module tree of the crate in `crate_dir`.
+///
+/// # Parameters
+/// - `crate_dir`: The crate directory, or the path of its `Cargo.toml`.
+/// - `options`: Selects the target with `lib` or `bin` and enables `#[cfg(test)]`
+///   items with `cfg_test`. `package` is not supported.
+///
+/// # Returns
+/// - `Ok(Extraction)` with the tree and the files it was read from.
+/// - `Err(io::Error)` of kind `NotFound` if the target has no root file.
+/// - `Err(io::Error)` of kind `InvalidData` if a file cannot be parsed, with the file,
+///   line and column of the error, or if `mod` declarations form a cycle, e.g. through
+///   `#[path = "lib.rs"] mod me;` in `src/lib.rs`.
+///
+/// # Details
+/// - Without `lib` or `bin`, the library target is used if the crate has one,
+///   otherwise the binary in `src/main.rs`.
+/// - Items behind `#[cfg(test)]` are skipped unless `cfg_test` is set, like cargo-modules does.
+pub fn extract(crate_dir: &Path, options: &StructureOptions) -> io::Result<Extraction> {
+    if options.package.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the native extractor analyzes a single crate; pass its directory instead of --package",
+        ));
+    }
+
+    let crate_dir = manifest_dir(crate_dir);
+    let manifest = load_manifest(crate_dir)?;
+    let (name, root_file) = root_target(crate_dir, &manifest, options)?;
+    extract_from_root(&name, &root_file, options.cfg_test)
+}
+
+/// Extracts the module tree of a crate from its root file.
+///
+/// # Parameters
+/// - `crate_name`: The name of the crate root node.
+/// - `root_file`: The crate root file, e.g. `src/lib.rs`.
+/// - `cfg_test`: Whether items behind `#[cfg(test)]` are included.
+pub fn extract_from_root(
+    crate_name: &str,
+    root_file: &Path,
+    cfg_test: bool,
+) -> io::Result<Extraction> {
+    let mut extractor = Extractor {
+        cfg_test,
+        files: Vec::new(),
+        missing: Vec::new(),
+        active: Vec::new(),
+    };
+    let mut root = Node::new(ItemKind::Crate, crate_name);
+    let module_dir = root_file.parent().unwrap_or(Path::new("")).to_path_buf();
+    root.children = extractor.file_items(root_file, "crate", &module_dir)?;
+
+    Ok(Extraction {
+        tree: ModuleTree { root },
+        root_file: root_file.to_path_buf(),
+        files: extractor.files,
+        missing: extractor.missing,
+    })
+}
+
+/// Extracts the module trees of every library and binary target of a crate.
+///
+/// # Parameters
+/// - `crate_dir`: The crate directory, or the path of its `Cargo.toml`.
+/// - `cfg_test`: Whether items behind `#[cfg(test)]` are included.
+///
+/// # Returns
+/// - `Ok(Vec<Extraction>)` with one extraction per target whose root file exists: the
+///   library, `src/main.rs`, `src/bin/*.rs`, `src/bin/*/main.rs` and `[[bin]]` targets.
+/// - `Err(io::Error)` if the manifest or a source file cannot be read or parsed.
+pub fn extract_targets(crate_dir: &Path, cfg_test: bool) -> io::Result<Vec<Extraction>> {
+    let crate_dir = manifest_dir(crate_dir);
+    let manifest = load_manifest(crate_dir)?;
+    let package = package_name(crate_dir, &manifest);
+
+    let mut targets = vec![
+        lib_target(crate_dir, &manifest, &package),
+        bin_target(crate_dir, &manifest, &package, &package),
+    ];
+    for target in &manifest.bin {
+        if let Some(name) = &target.name {
+            targets.push(bin_target(crate_dir, &manifest, &package, name));
+        }
+    }
+    if let Ok(entries) = fs::read_dir(crate_dir.join("src/bin")) {
+        let mut bin_files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        bin_files.sort();
+        for path in bin_files {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned());
+            if let Some(name) =
+                name.filter(|_| path.is_dir() || path.extension().is_some_and(|ext| ext == "rs"))
+            {
+                targets.push(bin_target(crate_dir, &manifest, &package, &name));
+            }
+        }
+    }
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let mut extractions = Vec::new();
+    for (name, root_file) in targets {
+        if !root_file.is_file() || roots.contains(&root_file) {
+            continue;
+        }
+        roots.push(root_file.clone());
+        extractions.push(extract_from_root(&name, &root_file, cfg_test)?);
+    }
+    Ok(extractions)
+}
+
+/// Returns the crate directory of a directory or `Cargo.toml` path.
+pub(crate) fn manifest_dir(path: &Path) -> &Path {
+    match path.file_name() {
+        Some(name) if name == "Cargo.toml" => path.parent().unwrap_or(Path::new("")),
+        _ => path,
+    }
+}
+
+/// Reads `Cargo.toml` in `crate_dir`, or returns an empty manifest if there is none.
+fn load_manifest(crate_dir: &Path) -> io::Result<Manifest> {
+    let manifest_path = crate_dir.join("Cargo.toml");
+    match fs::read_to_string(&manifest_path) {
+        Ok(manifest) => toml::from_str(&manifest).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", manifest_path.display(), error.message()),
+            )
+        }),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the package name, or the name of the crate directory without a manifest.
+fn package_name(crate_dir: &Path, manifest: &Manifest) -> String {
+    match &manifest.package {
+        Some(package) => package.name.clone(),
+        None => crate_dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "crate".to_string()),
+    }
+}
+
+/// Returns the crate name and root file of the library target.
+fn lib_target(crate_dir: &Path, manifest: &Manifest, package: &str) -> (String, PathBuf) {
+    let target = manifest.lib.as_ref();
+    let path = target.and_then(|target| target.path.clone());
+    let name = target.and_then(|target| target.name.clone());
+    (
+        crate_name(name.as_deref().unwrap_or(package)),
+        crate_dir.join(path.unwrap_or_else(|| PathBuf::from("src/lib.rs"))),
+    )
+}
+
+/// Returns the crate name and root file of the binary target `name`.
+fn bin_target(
+    crate_dir: &Path,
+    manifest: &Manifest,
+    package: &str,
+    name: &str,
+) -> (String, PathBuf) {
+    let target = manifest
+        .bin
+        .iter()
+        .find(|target| target.name.as_deref() == Some(name));
+    let path = match target.and_then(|target| target.path.clone()) {
+        Some(path) => crate_dir.join(path),
+        None if name == package => crate_dir.join("src/main.rs"),
+        None => {
+            let file = crate_dir.join("src/bin").join(format!("{}.rs", name));
+            if file.is_file() {
+                file
+            } else {
+                crate_dir.join("src/bin").join(name).join("main.rs")
+            }
+        }
+    };
+    (crate_name(name), path)
+}
+
+/// Returns the crate name of a package or target name, e.g. `my_crate` for `my-crate`.
+fn crate_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Returns the crate name and root file of the target selected by `options`.
+fn root_target(
+    crate_dir: &Path,
+    manifest: &Manifest,
+    options: &StructureOptions,
+) -> io::Result<(String, PathBuf)> {
+    let package = package_name(crate_dir, manifest);
+    let (name, root_file) = match (&options.bin, options.lib) {
+        (Some(name), _) => bin_target(crate_dir, manifest, &package, name),
+        (None, true) => lib_target(crate_dir, manifest, &package),
+        (None, false) => match lib_target(crate_dir, manifest, &package) {
+            (name, file) if file.is_file() => (name, file),
+            _ => bin_target(crate_dir, manifest, &package, &package),
+        },
+    };
+    if !root_file.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("crate root file {} not found", root_file.display()),
+        ));
+    }
+    Ok((name, root_file))
+}
+
+/// Walks the modules of a crate, one source file at a time.
+struct Extractor {
+    cfg_test: bool,
+    files: Vec<PathBuf>,
+    missing: Vec<MissingModule>,
+    /// The canonical files of the modules from the crate root to the current one.
+    active: Vec<PathBuf>,
+}
+
+impl Extractor {
+    /// Parses a source file and returns the nodes of its items.
+    ///
+    /// # Parameters
+    /// - `file`: The source file of the module.
+    /// - `path`: The path of the module, e.g. `crate::a`.
+    /// - `module_dir`: The directory that holds the files of its child modules.
+    ///
+    /// # Returns
+    /// - `Err(io::Error)` of kind `InvalidData` if `file` is already one of the files
+    ///   on the current module path, which would recurse forever.
+    fn file_items(&mut self, file: &Path, path: &str, module_dir: &Path) -> io::Result<Vec<Node>> {
+        let canonical = fs::canonicalize(file)?;
+        if let Some(start) = self.active.iter().position(|active| *active == canonical) {
+            let cycle: Vec<String> = self.active[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "`mod` declarations form a cycle at `{}`: {}",
+                    path,
+                    cycle.join(" -> ")
+                ),
+            ));
+        }
+
+        self.active.push(canonical);
+        let nodes = self.parse_file_items(file, path, module_dir);
+        self.active.pop();
+        nodes
+    }
+
+    /// Parses a source file that is not on the current module path.
+    fn parse_file_items(
+        &mut self,
+        file: &Path,
+        path: &str,
+        module_dir: &Path,
+    ) -> io::Result<Vec<Node>> {
+        self.files.push(file.to_path_buf());
+        let source = fs::read_to_string(file)?;
+        let syntax = syn::parse_file(&source).map_err(|error| {
+            let start = error.span().start();
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{}:{}:{}: {}",
+                    file.display(),
+                    start.line,
+                    start.column + 1,
+                    error
+                ),
+            )
+        })?;
+        self.items(&syntax.items, file, path, module_dir)
+    }
+
+    /// Returns the nodes of the items of a module.
+    fn items(
+        &mut self,
+        items: &[syn::Item],
+        file: &Path,
+        path: &str,
+        module_dir: &Path,
+    ) -> io::Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        for item in items {
+            let Some(mut node) = item_node(item) else {
+                continue;
+            };
+            let attributes = item_attributes(item);
+            if !self.cfg_test && attributes.iter().any(is_cfg_test) {
+                continue;
+            }
+            node.attributes = attributes.iter().filter_map(attribute_text).collect();
+
+            if let syn::Item::Mod(module) = item {
+                let child_path = Node::child_path(path, &node);
+                let name = module.ident.to_string();
+                let path_attribute = path_attribute(&module.attrs);
+                node.children = match &module.content {
+                    // Inline modules keep their items in this file
+                    Some((_, items)) => {
+                        let dir = match &path_attribute {
+                            Some(custom) => module_dir.join(custom),
+                            None => module_dir.join(&name),
+                        };
+                        self.items(items, file, &child_path, &dir)?
+                    }
+                    None => {
+                        self.module_file(file, &child_path, module_dir, &name, path_attribute)?
+                    }
+                };
+            }
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    /// Resolves and parses the file of a `mod name;` declaration.
+    fn module_file(
+        &mut self,
+        declared_in: &Path,
+        path: &str,
+        module_dir: &Path,
+        name: &str,
+        path_attribute: Option<String>,
+    ) -> io::Result<Vec<Node>> {
+        let candidates = match path_attribute {
+            // `#[path]` is relative to the directory of the declaring file
+            Some(custom) => vec![declared_in.parent().unwrap_or(Path::new("")).join(custom)],
+            None => vec![
+                module_dir.join(format!("{}.rs", name)),
+                module_dir.join(name).join("mod.rs"),
+            ],
+        };
+        let Some(file) = candidates.iter().find(|candidate| candidate.is_file()) else {
+            self.missing.push(MissingModule {
+                path: path.to_string(),
+                declared_in: declared_in.to_path_buf(),
+                candidates,
+            });
+            return Ok(Vec::new());
+        };
+
+        // Only `mod.rs` files keep their child modules next to themselves
+        let file = file.clone();
+        let child_dir = if file.file_name().is_some_and(|name| name == "mod.rs") {
+            file.parent().unwrap_or(Path::new("")).to_path_buf()
+        } else {
+            file.with_extension("")
+        };
+        self.file_items(&file, path, &child_dir)
+    }
+}
+
+/// Creates the node of an item, or returns `None` for items without a tree entry
+/// such as `use` declarations and `impl` blocks.
+fn item_node(item: &syn::Item) -> Option<Node> {
+    let (kind, ident, visibility) = match item {
+        syn::Item::Mod(item) => (ItemKind::Mod, &item.ident, &item.vis),
+        syn::Item::Struct(item) => (ItemKind::Struct, &item.ident, &item.vis),
+        syn::Item::Enum(item) => (ItemKind::Enum, &item.ident, &item.vis),
+        syn::Item::Union(item) => (ItemKind::Union, &item.ident, &item.vis),
+        syn::Item::Trait(item) => (ItemKind::Trait, &item.ident, &item.vis),
+        syn::Item::TraitAlias(item) => (ItemKind::Trait, &item.ident, &item.vis),
+        syn::Item::Fn(item) => (ItemKind::Fn, &item.sig.ident, &item.vis),
+        syn::Item::Type(item) => (ItemKind::Type, &item.ident, &item.vis),
+        syn::Item::Const(item) => (ItemKind::Const, &item.ident, &item.vis),
+        syn::Item::Static(item) => (ItemKind::Static, &item.ident, &item.vis),
+        syn::Item::Macro(item) => {
+            let ident = item.ident.as_ref()?;
+            let exported = item
+                .attrs
+                .iter()
+                .any(|attribute| attribute.path().is_ident("macro_export"));
+            let mut node = Node::new(ItemKind::Macro, ident.to_string());
+            node.visibility = Some(if exported {
+                Visibility::Public
+            } else {
+                Visibility::Private
+            });
+            return Some(node);
+        }
+        _ => return None,
+    };
+
+    // Unnamed constants such as `const _: () = …;` have no path
+    if ident == "_" {
+        return None;
+    }
+    let mut node = Node::new(kind, ident.to_string());
+    node.visibility = Some(visibility_of(visibility));
+    Some(node)
+}
+
+fn item_attributes(item: &syn::Item) -> &[syn::Attribute] {
+    match item {
+        syn::Item::Mod(item) => &item.attrs,
+        syn::Item::Struct(item) => &item.attrs,
+        syn::Item::Enum(item) => &item.attrs,
+        syn::Item::Union(item) => &item.attrs,
+        syn::Item::Trait(item) => &item.attrs,
+        syn::Item::TraitAlias(item) => &item.attrs,
+        syn::Item::Fn(item) => &item.attrs,
+        syn::Item::Type(item) => &item.attrs,
+        syn::Item::Const(item) => &item.attrs,
+        syn::Item::Static(item) => &item.attrs,
+        syn::Item::Macro(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+fn visibility_of(visibility: &syn::Visibility) -> Visibility {
+    match visibility {
+        syn::Visibility::Public(_) => Visibility::Public,
+        syn::Visibility::Inherited => Visibility::Private,
+        syn::Visibility::Restricted(restricted) => {
+            let path = path_text(&restricted.path);
+            match (restricted.in_token.is_some(), path.as_str()) {
+                (false, "crate") => Visibility::Crate,
+                (false, "super") => Visibility::Super,
+                (false, "self") => Visibility::Private,
+                _ => Visibility::Restricted(path),
+            }
+        }
+    }
+}
+
+/// Returns the text of an attribute shown in the tree: `#[cfg(…)]` and `#[test]`.
+fn attribute_text(attribute: &syn::Attribute) -> Option<String> {
+    if attribute.path().is_ident("test") {
+        return Some("#[test]".to_string());
+    }
+    if !attribute.path().is_ident("cfg") {
+        return None;
+    }
+    let list = attribute.meta.require_list().ok()?;
+    Some(format!("#[cfg({})]", tokens_text(&list.tokens)))
+}
+
+fn is_cfg_test(attribute: &syn::Attribute) -> bool {
+    attribute_text(attribute).as_deref() == Some("#[cfg(test)]")
+}
+
+/// Returns the value of a `#[path = "…"]` attribute.
+fn path_attribute(attributes: &[syn::Attribute]) -> Option<String> {
+    attributes.iter().find_map(|attribute| {
+        let name_value = attribute.meta.require_name_value().ok()?;
+        if !name_value.path.is_ident("path") {
+            return None;
+        }
+        match &name_value.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(path),
+                ..
+            }) => Some(path.value()),
+            _ => None,
+        }
+    })
+}
+
+fn path_text(path: &syn::Path) -> String {
+    let segments: Vec<String> = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    let prefix = if path.leading_colon.is_some() {
+        "::"
+    } else {
+        ""
+    };
+    format!("{}{}", prefix, segments.join("::"))
+}
+
+/// Formats tokens the way they are written in source, e.g. `all(test, unix)`.
+fn tokens_text(tokens: &proc_macro2::TokenStream) -> String {
+    tokens
+        .to_string()
+        .replace(" (", "(")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a crate with the given source files in a fresh temporary directory.
+    fn temp_crate(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (file, source) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn path_attribute_cycle_is_an_error() {
+        let dir = temp_crate(
+            "extract-cycle",
+            &[
+                ("src/lib.rs", "pub mod a;\n"),
+                ("src/a.rs", "#[path = \"lib.rs\"]\nmod again;\n"),
+            ],
+        );
+        let error = extract_from_root("cycle", &dir.join("src/lib.rs"), false).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let message = error.to_string();
+        assert!(message.contains("crate::a::again"), "{}", message);
+        assert!(message.contains("lib.rs -> "), "{}", message);
+    }
+
+    #[test]
+    fn same_file_in_sibling_modules_is_not_a_cycle() {
+        let dir = temp_crate(
+            "extract-shared",
+            &[
+                (
+                    "src/lib.rs",
+                    "#[path = \"shared.rs\"]\nmod a;\n#[path = \"shared.rs\"]\nmod b;\n",
+                ),
+                ("src/shared.rs", "pub fn f() {}\n"),
+            ],
+        );
+        let extraction = extract_from_root("shared", &dir.join("src/lib.rs"), false).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let paths: Vec<String> = extraction
+            .tree
+            .nodes()
+            .into_iter()
+            .map(|(path, _, _)| path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "crate",
+                "crate::a",
+                "crate::a::f",
+                "crate::b",
+                "crate::b::f"
+            ]
+        );
+    }
+}
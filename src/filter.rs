@@ -0,0 +1,360 @@
+//! Filtering of the module tree by visibility, item kind and path pattern, and
+//! pruning by depth and collapsed subtrees.
+//!
+//! A node is kept when it matches every filter or when one of its descendants is
+//! kept, so the filtered tree stays connected from the crate root to every match.
+//! Pruned subtrees are counted in [`Node::collapsed`] so renderers can show how many
+//! items are hidden.
+
+use crate::tree::{ItemKind, ModuleTree, Node, Visibility};
+
+/// A glob over `::`-separated item paths, e.g. `crate::tests::**`.
+///
+/// # Details
+/// - `**` matches any number of path segments, including none.
+/// - Within a segment, `*` matches any run of characters and `?` a single character.
+/// - The pattern must match the whole path, which always starts with `crate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    AnyDepth,
+    Glob(Vec<char>),
+}
+
+impl PathPattern {
+    /// Creates a pattern from its text.
+    pub fn new(pattern: &str) -> Self {
+        let segments = pattern
+            .split("::")
+            .map(|segment| match segment {
+                "**" => Segment::AnyDepth,
+                glob => Segment::Glob(glob.chars().collect()),
+            })
+            .collect();
+        Self {
+            pattern: pattern.to_string(),
+            segments,
+        }
+    }
+
+    /// Returns the text of the pattern.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns `true` if the pattern matches the whole item path.
+    pub fn matches(&self, path: &str) -> bool {
+        let path: Vec<&str> = path.split("::").collect();
+        matches_segments(&self.segments, &path)
+    }
+}
+
+/// Filters applied to a module tree.
+///
+/// # Details
+/// - Empty filters keep every node; see [`TreeFilter::is_empty`].
+/// - The crate root is always kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeFilter {
+    /// Keep only `pub` items.
+    pub only_pub: bool,
+    /// Keep only items of these kinds; empty for every kind.
+    ///
+    /// An [`ItemKind::Other`] entry, whatever its keyword, stands for every kind
+    /// outside [`ItemKind::KEYWORDS`], so `--kinds other` selects them all.
+    pub kinds: Vec<ItemKind>,
+    /// Keep only items whose path matches one of these patterns; empty for every path.
+    pub include: Vec<PathPattern>,
+    /// Remove items whose path matches one of these patterns, with everything below them.
+    pub exclude: Vec<PathPattern>,
+    /// Hide everything below this depth, the crate root having depth 0.
+    pub max_depth: Option<usize>,
+    /// Hide everything below items whose path matches one of these patterns.
+    pub collapse: Vec<PathPattern>,
+}
+
+impl TreeFilter {
+    /// Returns `true` if the filter keeps every node.
+    pub fn is_empty(&self) -> bool {
+        !self.only_pub
+            && self.kinds.is_empty()
+            && self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.max_depth.is_none()
+            && self.collapse.is_empty()
+    }
+
+    /// Returns `true` if the node at `path` matches the visibility, kind and include filters.
+    pub fn matches(&self, path: &str, node: &Node) -> bool {
+        (!self.only_pub || node.visibility.as_ref().is_some_and(Visibility::is_public))
+            && (self.kinds.is_empty() || self.kinds.iter().any(|kind| selects(kind, &node.kind)))
+            && (self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(path)))
+    }
+
+    /// Returns `true` if the node at `path` is removed with its subtree.
+    pub fn excludes(&self, path: &str) -> bool {
+        self.exclude.iter().any(|pattern| pattern.matches(path))
+    }
+
+    /// Returns a copy of the tree that only holds matching nodes and their ancestors.
+    ///
+    /// # Details
+    /// - Depth limiting and collapsing apply after filtering, so the hidden item counts
+    ///   only include items that passed the filters.
+    pub fn apply(&self, tree: &ModuleTree) -> ModuleTree {
+        let children = self.filter_children("crate", 0, &tree.root);
+        ModuleTree {
+            root: self.prune("crate", 0, &tree.root, children),
+        }
+    }
+
+    /// Filters a node and its subtree.
+    ///
+    /// # Returns
+    /// - `Some(Node)` if the node matches or one of its descendants is kept.
+    /// - `None` if the node is excluded or nothing in its subtree matches.
+    fn filter_node(&self, path: &str, depth: usize, node: &Node) -> Option<Node> {
+        if self.excludes(path) {
+            return None;
+        }
+
+        let children = self.filter_children(path, depth, node);
+        if children.is_empty() && !self.matches(path, node) {
+            return None;
+        }
+        Some(self.prune(path, depth, node, children))
+    }
+
+    fn filter_children(&self, path: &str, depth: usize, node: &Node) -> Vec<Node> {
+        node.children
+            .iter()
+            .filter_map(|child| self.filter_node(&Node::child_path(path, child), depth + 1, child))
+            .collect()
+    }
+
+    /// Copies a node with its filtered children, hiding them if the node is pruned.
+    fn prune(&self, path: &str, depth: usize, node: &Node, children: Vec<Node>) -> Node {
+        let mut pruned = without_children(node);
+        pruned.children = children;
+        let collapse = self.max_depth.is_some_and(|max_depth| depth >= max_depth)
+            || self.collapse.iter().any(|pattern| pattern.matches(path));
+        if collapse {
+            pruned.collapsed = pruned.item_count();
+            pruned.children.clear();
+        }
+        pruned
+    }
+}
+
+/// Returns `true` if the kind filter entry `filter` selects items of kind `kind`.
+fn selects(filter: &ItemKind, kind: &ItemKind) -> bool {
+    match (filter, kind) {
+        (ItemKind::Other(_), ItemKind::Other(_)) => true,
+        (filter, kind) => filter == kind,
+    }
+}
+
+/// Copies a node without cloning its subtree.
+fn without_children(node: &Node) -> Node {
+    Node {
+        name: node.name.clone(),
+        kind: node.kind.clone(),
+        visibility: node.visibility.clone(),
+        attributes: node.attributes.clone(),
+        orphan: node.orphan,
+        children: Vec::new(),
+        collapsed: node.collapsed,
+    }
+}
+
+fn matches_segments(pattern: &[Segment], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((Segment::AnyDepth, rest)) => {
+            (0..=path.len()).any(|skipped| matches_segments(rest, &path[skipped..]))
+        }
+        Some((Segment::Glob(glob), rest)) => match path.split_first() {
+            Some((segment, path)) => {
+                let segment: Vec<char> = segment.chars().collect();
+                matches_glob(glob, &segment) && matches_segments(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches a single path segment against a glob with `*` and `?` wildcards.
+fn matches_glob(glob: &[char], text: &[char]) -> bool {
+    match glob.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skipped| matches_glob(rest, &text[skipped..])),
+        Some(('?', rest)) => !text.is_empty() && matches_glob(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && matches_glob(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> ModuleTree {
+        ModuleTree::parse(
+            "crate demo\n\
+             ├── mod cli: pub(crate)\n\
+             │   ├── struct Args: pub\n\
+             │   └── fn parse: pub(self)\n\
+             ├── mod tests: pub(self) #[cfg(test)]\n\
+             │   └── fn it_works: pub(self)\n\
+             └── builtin i32: pub\n",
+        )
+        .unwrap()
+    }
+
+    fn paths(tree: &ModuleTree) -> Vec<String> {
+        tree.nodes().into_iter().map(|(path, _, _)| path).collect()
+    }
+
+    #[test]
+    fn star_matches_within_one_segment() {
+        let pattern = PathPattern::new("crate::c*::?rgs");
+        assert!(pattern.matches("crate::cli::Args"));
+        assert!(pattern.matches("crate::c::Args"));
+        assert!(!pattern.matches("crate::cli::sub::Args"));
+        assert!(!pattern.matches("crate::cli::Arg"));
+        assert_eq!(pattern.as_str(), "crate::c*::?rgs");
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_segments() {
+        let pattern = PathPattern::new("crate::**::parse");
+        assert!(pattern.matches("crate::parse"));
+        assert!(pattern.matches("crate::cli::parse"));
+        assert!(pattern.matches("crate::a::b::c::parse"));
+        assert!(!pattern.matches("crate::cli::parse_args"));
+
+        let below = PathPattern::new("crate::cli::**");
+        assert!(below.matches("crate::cli"));
+        assert!(below.matches("crate::cli::Args"));
+        assert!(!below.matches("crate::client"));
+    }
+
+    #[test]
+    fn patterns_match_the_whole_path() {
+        assert!(!PathPattern::new("cli").matches("crate::cli"));
+        assert!(!PathPattern::new("crate::cli").matches("crate::cli::Args"));
+        assert!(!PathPattern::new("crate::cli").matches("crate"));
+        assert!(PathPattern::new("**::Args").matches("crate::cli::Args"));
+    }
+
+    #[test]
+    fn kinds_keep_matches_and_their_ancestors() {
+        let filter = TreeFilter {
+            kinds: vec![ItemKind::Struct],
+            ..TreeFilter::default()
+        };
+        assert_eq!(
+            paths(&filter.apply(&tree())),
+            ["crate", "crate::cli", "crate::cli::Args"]
+        );
+    }
+
+    #[test]
+    fn other_kind_selects_every_unknown_kind() {
+        let filter = TreeFilter {
+            kinds: vec![ItemKind::from_keyword("other")],
+            ..TreeFilter::default()
+        };
+        assert_eq!(paths(&filter.apply(&tree())), ["crate", "crate::i32"]);
+    }
+
+    #[test]
+    fn visibility_include_and_exclude_filters_combine() {
+        let only_pub = TreeFilter {
+            only_pub: true,
+            ..TreeFilter::default()
+        };
+        assert_eq!(
+            paths(&only_pub.apply(&tree())),
+            ["crate", "crate::cli", "crate::cli::Args", "crate::i32"]
+        );
+
+        let include = TreeFilter {
+            include: vec![PathPattern::new("crate::**::it_works")],
+            ..TreeFilter::default()
+        };
+        assert_eq!(
+            paths(&include.apply(&tree())),
+            ["crate", "crate::tests", "crate::tests::it_works"]
+        );
+
+        let exclude = TreeFilter {
+            exclude: vec![PathPattern::new("crate::tests")],
+            ..TreeFilter::default()
+        };
+        assert_eq!(
+            paths(&exclude.apply(&tree())),
+            [
+                "crate",
+                "crate::cli",
+                "crate::cli::Args",
+                "crate::cli::parse",
+                "crate::i32"
+            ]
+        );
+        assert!(TreeFilter::default().is_empty());
+        assert!(!exclude.is_empty());
+    }
+
+    #[test]
+    fn max_depth_hides_and_counts_deeper_items() {
+        let filter = TreeFilter {
+            max_depth: Some(1),
+            ..TreeFilter::default()
+        };
+        let pruned = filter.apply(&tree());
+        assert_eq!(
+            paths(&pruned),
+            ["crate", "crate::cli", "crate::tests", "crate::i32"]
+        );
+        let collapsed: Vec<usize> = pruned
+            .root
+            .children
+            .iter()
+            .map(|node| node.collapsed)
+            .collect();
+        assert_eq!(collapsed, [2, 1, 0]);
+        assert_eq!(pruned.root.item_count(), tree().root.item_count());
+
+        let root_only = TreeFilter {
+            max_depth: Some(0),
+            ..TreeFilter::default()
+        };
+        let pruned = root_only.apply(&tree());
+        assert!(pruned.root.children.is_empty());
+        assert_eq!(pruned.root.collapsed, 6);
+    }
+
+    #[test]
+    fn collapse_counts_only_items_that_pass_the_filters() {
+        let filter = TreeFilter {
+            only_pub: true,
+            collapse: vec![PathPattern::new("crate::cli")],
+            ..TreeFilter::default()
+        };
+        let pruned = filter.apply(&tree());
+        assert_eq!(paths(&pruned), ["crate", "crate::cli", "crate::i32"]);
+        assert_eq!(pruned.root.children[0].collapsed, 1);
+
+        // Counts of an already pruned tree are carried over
+        let again = TreeFilter {
+            max_depth: Some(0),
+            ..TreeFilter::default()
+        };
+        assert_eq!(again.apply(&pruned).root.collapsed, 3);
+    }
+}
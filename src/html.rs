@@ -0,0 +1,246 @@
+//! Conversion of colored terminal output to a standalone HTML page.
+//!
+//! SGR sequences become `<span>` elements, so the colors that cargo-modules uses for
+//! visibility and item kind survive. Every other escape sequence is removed.
+
+use crate::ansi::{self, Token};
+use crate::sgr::{Color, Style};
+use crate::Cleaner;
+use std::fmt::Write;
+
+/// Options of the HTML conversion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// The title of the page.
+    pub title: String,
+    /// Style spans with `style` attributes instead of classes and a stylesheet.
+    pub inline_styles: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            title: "Module structure".to_string(),
+            inline_styles: false,
+        }
+    }
+}
+
+/// Page colors, matching a dark terminal.
+const BACKGROUND: &str = "#1e1e1e";
+const FOREGROUND: &str = "#d4d4d4";
+
+/// Converts colored text to a standalone HTML page.
+///
+/// # Parameters
+/// - `input`: The text to convert, with its escape sequences.
+/// - `ascii`: A cleaner whose mapping replaces the box-drawing characters, or `None`
+///   to keep them.
+/// - `options`: The title and styling of the page.
+///
+/// # Returns
+/// - The page, with the text in a `<pre>` element.
+///
+/// # Details
+/// - The 16 standard colors, bold, italic and underline use classes such as
+///   `fg-green` and `bold`; 256-color and truecolor values always use inline styles.
+pub fn to_html(input: &str, ascii: Option<&Cleaner>, options: &HtmlOptions) -> String {
+    let mut html = String::with_capacity(input.len() * 2);
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    writeln!(html, "<title>{}</title>", escape(&options.title)).unwrap();
+    html.push_str("<style>\n");
+    writeln!(
+        html,
+        "body {{ background: {}; color: {}; }}",
+        BACKGROUND, FOREGROUND
+    )
+    .unwrap();
+    if !options.inline_styles {
+        html.push_str(&stylesheet());
+    }
+    html.push_str("</style>\n</head>\n<body>\n<pre>");
+    html.push_str(&to_html_fragment(input, ascii, options.inline_styles));
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+/// Converts colored text to HTML text with `<span>` elements, without a page around it.
+///
+/// # Parameters
+/// - `input`: The text to convert, with its escape sequences.
+/// - `ascii`: A cleaner whose mapping replaces the box-drawing characters, or `None`
+///   to keep them.
+/// - `inline_styles`: Style spans with `style` attributes instead of classes.
+pub fn to_html_fragment(input: &str, ascii: Option<&Cleaner>, inline_styles: bool) -> String {
+    let mut html = String::with_capacity(input.len() * 2);
+    let mut style = Style::default();
+    let mut open = false;
+
+    for token in ansi::tokenize(input) {
+        match token {
+            Token::Text(text) => {
+                if !open && !style.is_plain() {
+                    write!(html, "<span {}>", attributes(&style, inline_styles)).unwrap();
+                    open = true;
+                }
+                match ascii {
+                    Some(cleaner) => html.push_str(&escape(&cleaner.clean_str(text))),
+                    None => html.push_str(&escape(text)),
+                }
+            }
+            Token::Escape(sequence) => {
+                let previous = style;
+                if style.apply_sequence(&sequence) && style != previous && open {
+                    html.push_str("</span>");
+                    open = false;
+                }
+            }
+        }
+    }
+    if open {
+        html.push_str("</span>");
+    }
+    html
+}
+
+/// Returns the `class` or `style` attribute of a span.
+fn attributes(style: &Style, inline_styles: bool) -> String {
+    let mut classes = Vec::new();
+    let mut declarations = Vec::new();
+    let mut color = |property: &str, prefix: &str, color: Option<Color>| match color
+        .and_then(Color::name)
+        .filter(|_| !inline_styles)
+    {
+        Some(name) => classes.push(format!("{}-{}", prefix, name)),
+        None => {
+            if let Some(color) = color {
+                declarations.push(format!("{}: {}", property, color.to_css()));
+            }
+        }
+    };
+    color("color", "fg", style.foreground);
+    color("background-color", "bg", style.background);
+
+    let flags = [
+        (style.bold, "bold", "font-weight: bold"),
+        (style.italic, "italic", "font-style: italic"),
+        (style.underline, "underline", "text-decoration: underline"),
+    ];
+    for (set, class, declaration) in flags {
+        match (set, inline_styles) {
+            (false, _) => {}
+            (true, false) => classes.push(class.to_string()),
+            (true, true) => declarations.push(declaration.to_string()),
+        }
+    }
+
+    let mut attributes = Vec::new();
+    if !classes.is_empty() {
+        attributes.push(format!("class=\"{}\"", classes.join(" ")));
+    }
+    if !declarations.is_empty() {
+        attributes.push(format!("style=\"{}\"", declarations.join("; ")));
+    }
+    attributes.join(" ")
+}
+
+/// Returns the rules for the classes of the 16 standard colors and the attributes.
+fn stylesheet() -> String {
+    let mut css = String::new();
+    for index in 0..16 {
+        let color = Color::Indexed(index);
+        let name = color.name().expect("standard colors have names");
+        writeln!(css, ".fg-{} {{ color: {}; }}", name, color.to_css()).unwrap();
+        writeln!(
+            css,
+            ".bg-{} {{ background-color: {}; }}",
+            name,
+            color.to_css()
+        )
+        .unwrap();
+    }
+    css.push_str(".bold { font-weight: bold; }\n");
+    css.push_str(".italic { font-style: italic; }\n");
+    css.push_str(".underline { text-decoration: underline; }\n");
+    css
+}
+
+/// Escapes the characters that are special in HTML text and attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_become_spans_with_classes() {
+        assert_eq!(
+            to_html_fragment("a \u{1b}[1;32mgreen\u{1b}[0m b", None, false),
+            "a <span class=\"fg-green bold\">green</span> b"
+        );
+        assert_eq!(
+            to_html_fragment("\u{1b}[38;5;208;48;2;1;2;3mx\u{1b}[m", None, false),
+            "<span style=\"color: #ff8700; background-color: #010203\">x</span>"
+        );
+    }
+
+    #[test]
+    fn inline_styles_replace_classes() {
+        assert_eq!(
+            to_html_fragment("\u{1b}[3;4;31mx\u{1b}[0m", None, true),
+            "<span style=\"color: #cd0000; font-style: italic; text-decoration: underline\">x</span>"
+        );
+    }
+
+    #[test]
+    fn style_changes_close_and_reopen_spans() {
+        assert_eq!(
+            to_html_fragment(
+                "\u{1b}[31mred \u{1b}[1mbold\u{1b}[1m same\u{1b}[22m red\u{1b}[2K end",
+                None,
+                false
+            ),
+            "<span class=\"fg-red\">red </span><span class=\"fg-red bold\">bold same</span>\
+             <span class=\"fg-red\"> red end</span>"
+        );
+        // A span left open at the end is closed
+        assert_eq!(
+            to_html_fragment("\u{1b}[32mopen", None, false),
+            "<span class=\"fg-green\">open</span>"
+        );
+    }
+
+    #[test]
+    fn text_and_title_are_escaped() {
+        let html = to_html_fragment("a<b> & \"c\" \u{1b}[31m<T>\u{1b}[0m", None, false);
+        assert_eq!(
+            html,
+            "a&lt;b&gt; &amp; &quot;c&quot; <span class=\"fg-red\">&lt;T&gt;</span>"
+        );
+
+        let options = HtmlOptions {
+            title: "<a & \"b\">".to_string(),
+            inline_styles: false,
+        };
+        let page = to_html("x", None, &options);
+        assert!(page.contains("<title>&lt;a &amp; &quot;b&quot;&gt;</title>"));
+        assert!(page.contains(".fg-bright-white { color: #ffffff; }"));
+        assert!(page.ends_with("<pre>x</pre>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn box_drawing_is_replaced_with_an_ascii_cleaner() {
+        let input = "├── \u{1b}[32mfn\u{1b}[0m <main>";
+        let cleaner = Cleaner::new();
+        assert_eq!(
+            to_html_fragment(input, Some(&cleaner), false),
+            "+-- <span class=\"fg-green\">fn</span> &lt;main&gt;"
+        );
+        assert!(to_html_fragment(input, None, false).starts_with("├── "));
+    }
+}
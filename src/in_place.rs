@@ -0,0 +1,126 @@
+use std::fs;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Replaces the contents of a file atomically.
+///
+/// # Parameters
+/// - `path`: The file to rewrite.
+/// - `backup_suffix`: If set, the original is first copied to `<path><suffix>`.
+/// - `write`: Writes the new contents. It may read `path`, which is unchanged until
+///   `write` returns successfully.
+///
+/// # Returns
+/// - `Ok(())` if the file was replaced.
+/// - `Err(io::Error)` if writing or renaming fails. The original file is left as it was.
+///
+/// # Details
+/// - The new contents go to a hidden temporary file in the same directory, which is
+///   renamed over the original, so an interrupted run never leaves a truncated file.
+/// - The permissions and the modification time of the original are restored where
+///   the platform allows it; failing to restore them is not an error.
+pub fn rewrite(
+    path: &Path,
+    backup_suffix: Option<&str>,
+    write: impl FnOnce(BufWriter<&mut NamedTempFile>) -> io::Result<()>,
+) -> io::Result<()> {
+    let metadata = fs::metadata(path)?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut temp = tempfile::Builder::new()
+        .prefix(".module_structure_cleaner-")
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    write(BufWriter::new(&mut temp))?;
+    temp.as_file().sync_all()?;
+    let _ = fs::set_permissions(temp.path(), metadata.permissions());
+
+    if let Some(suffix) = backup_suffix {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(suffix);
+        fs::copy(path, &backup)?;
+        restore_mtime(Path::new(&backup), &metadata);
+    }
+    temp.persist(path).map_err(|error| error.error)?;
+    restore_mtime(path, &metadata);
+    Ok(())
+}
+
+fn restore_mtime(path: &Path, metadata: &fs::Metadata) {
+    if let Ok(modified) = metadata.modified() {
+        let _ = filetime::set_file_mtime(path, filetime::FileTime::from_system_time(modified));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rewrite_keeps_backup_permissions_and_mtime() {
+        let dir = temp_dir("in-place-rewrite");
+        let path = dir.join("tree.txt");
+        fs::write(&path, "├── mod a\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+        let mtime = filetime::FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(&path, mtime).unwrap();
+        let before = fs::metadata(&path).unwrap();
+
+        rewrite(&path, Some(".bak"), |mut writer| {
+            let mut original = String::new();
+            fs::File::open(&path)?.read_to_string(&mut original)?;
+            writer.write_all(original.replace("├──", "+--").as_bytes())?;
+            writer.flush()
+        })
+        .unwrap();
+
+        let backup = dir.join("tree.txt.bak");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "+-- mod a\n");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "├── mod a\n");
+        for file in [&path, &backup] {
+            let after = fs::metadata(file).unwrap();
+            assert_eq!(after.permissions(), before.permissions());
+            assert_eq!(
+                filetime::FileTime::from_last_modification_time(&after),
+                mtime
+            );
+        }
+        // No temporary file is left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_write_leaves_the_file_unchanged() {
+        let dir = temp_dir("in-place-failure");
+        let path = dir.join("tree.txt");
+        fs::write(&path, "original\n").unwrap();
+
+        let error = rewrite(&path, Some(".bak"), |mut writer| {
+            writer.write_all(b"partial")?;
+            Err(io::Error::new(io::ErrorKind::InvalidData, "bad input"))
+        })
+        .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
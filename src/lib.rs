@@ -0,0 +1,629 @@
+//! Removes ANSI escape codes and replaces Unicode box-drawing characters with ASCII.
+//!
+//! The [`Cleaner`] type is the entry point of the library. It cleans string slices,
+//! streams from any [`BufRead`] into any [`Write`], or cleans whole files:
+//!
+//! ```
+//! use module_structure_cleaner::Cleaner;
+//!
+//! let cleaner = Cleaner::new();
+//! assert_eq!(cleaner.clean_str("\x1b[32m├── fn main\x1b[0m"), "+-- fn main");
+//! ```
+//!
+//! Escape sequences are recognized by the ECMA-48 parser in the [`ansi`] module, and
+//! box-drawing characters are replaced using the table in the [`box_drawing`] module.
+//! The replacements can be changed with a [`CharMap`] from the [`mapping`] module.
+//!
+//! The [`tree`] module parses `cargo modules structure` output into a typed
+//! [`ModuleTree`](tree::ModuleTree) for analysis. The [`export`] module writes that
+//! tree as JSON or YAML and the [`diagram`] module renders it as a diagram. The
+//! [`diff`] module compares two trees, the [`filter`] module prunes a tree and the
+//! [`render`] module draws a tree as text. The [`cargo_modules`] module runs
+//! `cargo modules structure` to produce the input in the first place, and the
+//! [`extract`] module builds the same tree from the sources without external tools.
+//! The [`orphans`] module uses it to find source files that no module reaches.
+//! The [`encoding`] module transcodes input and output that is not UTF-8. The
+//! [`sgr`] module interprets color sequences instead of removing them, which the
+//! [`html`] module uses to keep the colors in an HTML page and the [`markers`]
+//! module to turn visibility colors into textual markers.
+
+pub mod ansi;
+pub mod box_drawing;
+pub mod cargo_modules;
+pub mod diagram;
+pub mod diff;
+pub mod encoding;
+pub mod export;
+pub mod extract;
+pub mod filter;
+pub mod html;
+pub mod mapping;
+pub mod markers;
+pub mod orphans;
+pub mod render;
+pub mod sgr;
+pub mod tree;
+
+pub use ansi::{EscapeSequence, SequenceKind};
+pub use mapping::CharMap;
+
+use mapping::CompiledMap;
+use markers::VisibilityMarkers;
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Cleans text by removing ANSI escape codes and replacing Unicode box-drawing characters.
+///
+/// # Details
+/// - Options are set with builder-style methods that consume and return the cleaner.
+/// - A `Cleaner` holds no per-run state, so one instance can clean any number of inputs.
+/// - Cleaning is a single pass over the input: escape sequences are skipped, runs of
+///   plain ASCII are copied unchanged and every other character is looked up in a
+///   table compiled from the [`CharMap`].
+/// - Either half can be turned off with [`Cleaner::strip_escapes`] or
+///   [`Cleaner::transliterate`], e.g. to keep colors for a console that cannot show
+///   box-drawing characters.
+#[derive(Debug, Clone, Default)]
+pub struct Cleaner {
+    flush_lines: bool,
+    keep_escapes: bool,
+    keep_box_drawing: bool,
+    line_ending: Option<LineEnding>,
+    lossy: Option<String>,
+    markers: Option<VisibilityMarkers>,
+    table: CompiledMap,
+}
+
+impl Cleaner {
+    /// Creates a cleaner with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether [`Cleaner::clean_reader_to_writer`] flushes the writer after every line.
+    ///
+    /// # Details
+    /// - Enable this when the output is consumed while a long-running producer is
+    ///   still writing, for example in a shell pipeline.
+    pub fn flush_lines(mut self, flush_lines: bool) -> Self {
+        self.flush_lines = flush_lines;
+        self
+    }
+
+    /// Sets whether escape sequences are removed.
+    ///
+    /// # Details
+    /// - Defaults to `true`. With `false` the sequences are copied unchanged, so colors
+    ///   survive while box-drawing characters are still replaced, and no removed
+    ///   sequences are reported.
+    pub fn strip_escapes(mut self, strip_escapes: bool) -> Self {
+        self.keep_escapes = !strip_escapes;
+        self
+    }
+
+    /// Sets whether box-drawing characters are replaced using the mapping.
+    ///
+    /// # Details
+    /// - Defaults to `true`. With `false` all text is copied unchanged, so only the
+    ///   escape sequences are removed.
+    pub fn transliterate(mut self, transliterate: bool) -> Self {
+        self.keep_box_drawing = !transliterate;
+        self
+    }
+
+    /// Sets the line ending that [`Cleaner::clean_reader_to_writer`] writes.
+    ///
+    /// # Details
+    /// - `None`, the default, keeps the line ending of every input line.
+    /// - Either way a last line without a line ending is written without one.
+    pub fn line_ending(mut self, line_ending: Option<LineEnding>) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Sets whether invalid UTF-8 is replaced instead of failing the run.
+    ///
+    /// # Details
+    /// - `None`, the default, fails with an [`InvalidUtf8Error`] at the first invalid
+    ///   sequence.
+    /// - `Some(replacement)` replaces every invalid sequence with `replacement`, e.g.
+    ///   `"\u{FFFD}"`.
+    pub fn lossy(mut self, replacement: Option<String>) -> Self {
+        self.lossy = replacement;
+        self
+    }
+
+    /// Sets whether [`Cleaner::clean_reader_to_writer`] appends the visibility that
+    /// the colors of a line stand for, e.g. ` [pub(crate)]`.
+    ///
+    /// # Details
+    /// - `None`, the default, appends nothing.
+    /// - Lines without a colored marker are left as they are.
+    pub fn visibility_markers(mut self, markers: Option<VisibilityMarkers>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Sets the character mapping used to replace box-drawing characters.
+    ///
+    /// # Details
+    /// - Defaults to the built-in table, see [`CharMap::new`].
+    pub fn mapping(mut self, mapping: CharMap) -> Self {
+        self.table = mapping.compile();
+        self
+    }
+
+    /// Cleans a string slice.
+    ///
+    /// # Parameters
+    /// - `input`: The text to clean. It may contain any number of lines.
+    ///
+    /// # Returns
+    /// - A `String` containing the cleaned text.
+    pub fn clean_str(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        self.clean_into(input, &mut output);
+        output
+    }
+
+    /// Cleans a string slice and appends the result to `output`.
+    ///
+    /// # Parameters
+    /// - `input`: The text to clean. It may contain any number of lines.
+    /// - `output`: The buffer that receives the cleaned text.
+    ///
+    /// # Details
+    /// - Reusing `output` across calls avoids allocating a new buffer per input.
+    pub fn clean_into(&self, input: &str, output: &mut String) {
+        self.clean_with(input, output, |_| {});
+    }
+
+    /// Cleans a string slice and reports every escape sequence that was removed.
+    ///
+    /// # Parameters
+    /// - `input`: The text to clean. It may contain any number of lines.
+    ///
+    /// # Returns
+    /// - The cleaned text and the removed escape sequences in input order.
+    pub fn clean_str_with_report<'a>(&self, input: &'a str) -> (String, Vec<EscapeSequence<'a>>) {
+        let mut removed = Vec::new();
+        let mut cleaned = String::with_capacity(input.len());
+        self.clean_with(input, &mut cleaned, |sequence| removed.push(sequence));
+        (cleaned, removed)
+    }
+
+    /// Cleans every line read from `reader` and writes the result to `writer`.
+    ///
+    /// # Parameters
+    /// - `reader`: The source of the text to clean.
+    /// - `writer`: The destination of the cleaned text.
+    ///
+    /// # Returns
+    /// - `Ok(())` if all lines were cleaned and written.
+    /// - `Err(io::Error)` if reading or writing fails.
+    pub fn clean_reader_to_writer(
+        &self,
+        reader: impl BufRead,
+        writer: impl Write,
+    ) -> io::Result<()> {
+        self.clean_reader_to_writer_with_report(reader, writer, |_| {})
+    }
+
+    /// Cleans every line read from `reader`, writes the result to `writer` and reports
+    /// every escape sequence that was removed.
+    ///
+    /// # Parameters
+    /// - `reader`: The source of the text to clean.
+    /// - `writer`: The destination of the cleaned text.
+    /// - `report`: Called with each removed escape sequence and its position.
+    ///
+    /// # Returns
+    /// - `Ok(())` if all lines were cleaned and written.
+    /// - `Err(io::Error)` if reading or writing fails.
+    /// - `Err(io::Error)` of kind `InvalidData` wrapping an [`InvalidUtf8Error`] if the
+    ///   input is not valid UTF-8 and the cleaner is not [lossy](Cleaner::lossy). The
+    ///   lines before the invalid one are written.
+    ///
+    /// # Details
+    /// - Input is parsed line by line, so a string sequence such as an OSC that is not
+    ///   terminated on its own line is removed up to the end of that line.
+    /// - Lines end with `\n` or `\r\n` as in the input, unless a line ending is set
+    ///   with [`Cleaner::line_ending`].
+    pub fn clean_reader_to_writer_with_report(
+        &self,
+        mut reader: impl BufRead,
+        mut writer: impl Write,
+        mut report: impl FnMut(RemovedEscape),
+    ) -> io::Result<()> {
+        // Both buffers are reused for every line
+        let mut line = Vec::new();
+        let mut cleaned_line = String::new();
+        let mut line_number = 0;
+        let mut offset = 0;
+
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            line_number += 1;
+            let text = match self.decode(&line) {
+                Ok(text) => text,
+                Err((valid_up_to, len)) => {
+                    writer.flush()?;
+                    return Err(InvalidUtf8Error {
+                        line: line_number,
+                        offset: offset + valid_up_to,
+                        bytes: line[valid_up_to..valid_up_to + len].to_vec(),
+                    }
+                    .into());
+                }
+            };
+            offset += line.len();
+            let (content, ending) = split_line_ending(&text);
+
+            cleaned_line.clear();
+            self.clean_with(content, &mut cleaned_line, |sequence| {
+                report(RemovedEscape::new(line_number, &sequence))
+            });
+            if let Some(marker) = self
+                .markers
+                .as_ref()
+                .and_then(|markers| markers.marker(content))
+            {
+                cleaned_line.push_str(" [");
+                cleaned_line.push_str(marker);
+                cleaned_line.push(']');
+            }
+            match self.line_ending {
+                Some(line_ending) if !ending.is_empty() => {
+                    cleaned_line.push_str(line_ending.as_str())
+                }
+                _ => cleaned_line.push_str(ending),
+            }
+            writer.write_all(cleaned_line.as_bytes())?;
+            if self.flush_lines {
+                writer.flush()?;
+            }
+            line.clear();
+        }
+        writer.flush()
+    }
+
+    /// Reads all of `reader` as UTF-8, replacing invalid sequences if the cleaner is
+    /// [lossy](Cleaner::lossy).
+    ///
+    /// # Returns
+    /// - `Ok(String)` with the text read.
+    /// - `Err(io::Error)` if reading fails.
+    /// - `Err(io::Error)` of kind `InvalidData` wrapping an [`InvalidUtf8Error`] if the
+    ///   input is not valid UTF-8 and the cleaner is not lossy.
+    pub fn read_text(&self, mut reader: impl BufRead) -> io::Result<String> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        match self.decode(&bytes) {
+            Ok(text) => Ok(text.into_owned()),
+            Err((valid_up_to, len)) => {
+                let before = &bytes[..valid_up_to];
+                Err(InvalidUtf8Error {
+                    line: before.iter().filter(|&&byte| byte == b'\n').count() + 1,
+                    offset: valid_up_to,
+                    bytes: bytes[valid_up_to..valid_up_to + len].to_vec(),
+                }
+                .into())
+            }
+        }
+    }
+
+    /// Decodes UTF-8, replacing invalid sequences if the cleaner is lossy.
+    ///
+    /// # Returns
+    /// - `Ok(Cow<str>)`, borrowed if `bytes` is valid UTF-8.
+    /// - `Err((valid_up_to, len))` with the position and length of the first invalid
+    ///   sequence if the cleaner is not lossy.
+    ///
+    /// # Details
+    /// - A raw 8-bit CSI or OSC introducer, 0x9B or 0x9D, is decoded as U+009B or
+    ///   U+009D if it starts a complete sequence (see [`ansi::raw_c1_sequence_len`]),
+    ///   so that the sequence is recognized. Any other byte 0x80 to 0x9F is invalid.
+    fn decode<'b>(&self, bytes: &'b [u8]) -> Result<Cow<'b, str>, (usize, usize)> {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(text));
+        }
+
+        let mut text = String::with_capacity(bytes.len());
+        let mut position = 0;
+        for chunk in bytes.utf8_chunks() {
+            text.push_str(chunk.valid());
+            position += chunk.valid().len();
+            match (chunk.invalid(), &self.lossy) {
+                ([], _) => {}
+                (&[byte @ (0x9b | 0x9d)], _)
+                    if ansi::raw_c1_sequence_len(&bytes[position..]).is_some() =>
+                {
+                    text.push(char::from(byte))
+                }
+                (_, Some(replacement)) => text.push_str(replacement),
+                (invalid, None) => return Err((position, invalid.len())),
+            }
+            position += chunk.invalid().len();
+        }
+        Ok(Cow::Owned(text))
+    }
+
+    /// Cleans text by removing escape sequences and replacing box-drawing characters.
+    ///
+    /// # Parameters
+    /// - `input`: A string slice representing the text to be cleaned.
+    /// - `output`: The buffer that receives the cleaned text.
+    /// - `removed`: Called with each escape sequence that is removed.
+    ///
+    /// # Details
+    /// - Escape sequences of every ECMA-48 class are removed using the [`ansi`] parser,
+    ///   unless [`Cleaner::strip_escapes`] is disabled.
+    /// - Unicode box-drawing and block element characters are replaced with their ASCII
+    ///   equivalents from the compiled mapping table, unless
+    ///   [`Cleaner::transliterate`] is disabled.
+    fn clean_with<'a>(
+        &self,
+        input: &'a str,
+        output: &mut String,
+        mut removed: impl FnMut(EscapeSequence<'a>),
+    ) {
+        for token in ansi::tokenize(input) {
+            match token {
+                ansi::Token::Text(text) if self.keep_box_drawing => output.push_str(text),
+                ansi::Token::Text(text) => self.table.transliterate_into(text, output),
+                ansi::Token::Escape(sequence) if self.keep_escapes => output.push_str(sequence.raw),
+                ansi::Token::Escape(sequence) => removed(sequence),
+            }
+        }
+    }
+
+    /// Cleans the file at `input` and writes the result to a new file at `output`.
+    ///
+    /// # Parameters
+    /// - `input`: The file to clean.
+    /// - `output`: The file to create or truncate with the cleaned text.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the file was cleaned successfully.
+    /// - `Err(io::Error)` if opening, reading or writing a file fails.
+    pub fn clean_file(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> io::Result<()> {
+        let reader = BufReader::new(File::open(input)?);
+        let writer = BufWriter::new(File::create(output)?);
+        self.clean_reader_to_writer(reader, writer)
+    }
+}
+
+/// A line ending style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`, as used on Unix.
+    Lf,
+    /// `\r\n`, as used on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the line ending of the platform this program was built for.
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Returns the line ending of the first line of `text`.
+    ///
+    /// # Returns
+    /// - `None` if `text` has no line ending at all.
+    pub fn detect(text: &str) -> Option<Self> {
+        let end = text.find('\n')?;
+        match text[..end].ends_with('\r') {
+            true => Some(LineEnding::CrLf),
+            false => Some(LineEnding::Lf),
+        }
+    }
+
+    /// Returns the characters of the line ending.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    /// Returns `text` with every `\n` and `\r\n` replaced by this line ending.
+    pub fn apply(self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        for line in text.split_inclusive('\n') {
+            let (content, ending) = split_line_ending(line);
+            output.push_str(content);
+            if !ending.is_empty() {
+                output.push_str(self.as_str());
+            }
+        }
+        output
+    }
+}
+
+/// Splits a line into its content and its `\n` or `\r\n` ending, which may be empty.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    let content = line
+        .strip_suffix("\r\n")
+        .or_else(|| line.strip_suffix('\n'))
+        .unwrap_or(line);
+    line.split_at(content.len())
+}
+
+/// The error wrapped in an [`io::Error`] when the input is not valid UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidUtf8Error {
+    /// The 1-based line number of the invalid sequence.
+    pub line: usize,
+    /// The 0-based byte offset of the invalid sequence from the start of the input.
+    pub offset: usize,
+    /// The bytes of the invalid sequence.
+    pub bytes: Vec<u8>,
+}
+
+impl fmt::Display for InvalidUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid UTF-8 on line {} at byte offset {}: ",
+            self.line, self.offset
+        )?;
+        for byte in &self.bytes {
+            write!(f, "\\x{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidUtf8Error {}
+
+impl From<InvalidUtf8Error> for io::Error {
+    fn from(error: InvalidUtf8Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// An escape sequence removed by [`Cleaner::clean_reader_to_writer_with_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedEscape {
+    /// The 1-based line number of the sequence.
+    pub line: usize,
+    /// The 1-based byte column of the sequence within its line.
+    pub column: usize,
+    /// The class of the sequence.
+    pub kind: SequenceKind,
+    /// The complete sequence, including its introducer and terminator.
+    pub raw: String,
+    /// `false` if the line ended before the sequence was terminated.
+    pub terminated: bool,
+}
+
+impl RemovedEscape {
+    fn new(line: usize, sequence: &EscapeSequence<'_>) -> Self {
+        Self {
+            line,
+            column: sequence.range.start + 1,
+            kind: sequence.kind,
+            raw: sequence.raw.to_string(),
+            terminated: sequence.terminated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean_bytes(cleaner: &Cleaner, input: &[u8]) -> io::Result<String> {
+        let mut output = Vec::new();
+        cleaner.clean_reader_to_writer(input, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn raw_c1_bytes_start_sequences() {
+        let cleaner = Cleaner::new();
+        let input = b"plain \x9b31mred\x9b0m \xe2\x94\x9c\n";
+        assert_eq!(clean_bytes(&cleaner, input).unwrap(), "plain red +\n");
+        assert_eq!(
+            cleaner.read_text(&input[..]).unwrap(),
+            "plain \u{9b}31mred\u{9b}0m ├\n"
+        );
+    }
+
+    #[test]
+    fn raw_osc_with_bel_is_a_sequence() {
+        let input = b"\x9d0;title\x07text\n";
+        assert_eq!(clean_bytes(&Cleaner::new(), input).unwrap(), "text\n");
+    }
+
+    #[test]
+    fn incomplete_raw_c1_bytes_are_invalid() {
+        // 0x9B without a final byte, and 0x9E that would open a privacy message
+        for input in [&b"a\x9b31\n"[..], b"Z\x9Eilina rest of line\n"] {
+            let error = clean_bytes(&Cleaner::new(), input).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        let lossy = Cleaner::new().lossy(Some("?".to_string()));
+        assert_eq!(
+            clean_bytes(&lossy, b"Z\x9Eilina rest of line\n").unwrap(),
+            "Z?ilina rest of line\n"
+        );
+    }
+
+    #[test]
+    fn windows_1252_punctuation_is_not_a_control() {
+        let input = b"name \x93quoted\x94 done\n";
+        let error = clean_bytes(&Cleaner::new(), input).unwrap_err();
+        let error = error.into_inner().unwrap();
+        let error = error.downcast_ref::<InvalidUtf8Error>().unwrap();
+        assert_eq!((error.line, error.offset), (1, 5));
+        assert_eq!(error.bytes, [0x93]);
+
+        let lossy = Cleaner::new().lossy(Some("\u{fffd}".to_string()));
+        assert_eq!(
+            clean_bytes(&lossy, input).unwrap(),
+            "name \u{fffd}quoted\u{fffd} done\n"
+        );
+    }
+
+    #[test]
+    fn other_invalid_bytes_are_reported() {
+        let error = clean_bytes(&Cleaner::new(), b"ok\n\x9b1mbad\xff\n").unwrap_err();
+        let error = error.into_inner().unwrap();
+        let error = error.downcast_ref::<InvalidUtf8Error>().unwrap();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.offset, 9);
+        assert_eq!(error.bytes, [0xff]);
+
+        let lossy = Cleaner::new().lossy(Some("?".to_string()));
+        assert_eq!(clean_bytes(&lossy, b"\x9b1mbad\xff\n").unwrap(), "bad?\n");
+    }
+
+    #[test]
+    fn line_endings_are_converted() {
+        let input = b"a\r\nb\nc\r\n";
+        let lf = Cleaner::new().line_ending(Some(LineEnding::Lf));
+        assert_eq!(clean_bytes(&lf, input).unwrap(), "a\nb\nc\n");
+        let crlf = Cleaner::new().line_ending(Some(LineEnding::CrLf));
+        assert_eq!(clean_bytes(&crlf, input).unwrap(), "a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn mixed_line_endings_are_preserved_by_default() {
+        let input = b"\x1b[1ma\x1b[0m\r\n\xe2\x94\x9c b\n\r\nc\r";
+        assert_eq!(
+            clean_bytes(&Cleaner::new(), input).unwrap(),
+            "a\r\n+ b\n\r\nc\r"
+        );
+    }
+
+    #[test]
+    fn missing_final_newline_is_not_added() {
+        for line_ending in [None, Some(LineEnding::Lf), Some(LineEnding::CrLf)] {
+            let cleaner = Cleaner::new().line_ending(line_ending);
+            assert_eq!(clean_bytes(&cleaner, b"").unwrap(), "");
+            assert_eq!(clean_bytes(&cleaner, b"last").unwrap(), "last");
+        }
+        let crlf = Cleaner::new().line_ending(Some(LineEnding::CrLf));
+        assert_eq!(clean_bytes(&crlf, b"a\nb").unwrap(), "a\r\nb");
+    }
+
+    #[test]
+    fn line_ending_is_detected_and_applied() {
+        assert_eq!(LineEnding::detect("a\r\nb\n"), Some(LineEnding::CrLf));
+        assert_eq!(LineEnding::detect("a\nb\r\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a\rb"), None);
+        assert_eq!(LineEnding::CrLf.apply("a\nb\r\nc"), "a\r\nb\r\nc");
+        assert_eq!(LineEnding::Lf.apply("a\r\n\r\nb\n"), "a\n\nb\n");
+    }
+}
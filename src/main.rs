@@ -1,125 +1,677 @@
-use rfd::FileDialog;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::PathBuf;
+mod batch;
+mod cli;
+mod in_place;
+mod watch;
+
+use batch::Summary;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use cli::{CheckOrphansArgs, Cli, Command, DiffArgs, DiffFormat, Format, GenerateArgs};
+use module_structure_cleaner::cargo_modules;
+use module_structure_cleaner::diff::TreeDiff;
+use module_structure_cleaner::encoding;
+use module_structure_cleaner::extract;
+use module_structure_cleaner::html;
+use module_structure_cleaner::mapping::{MappingFile, PRESETS};
+use module_structure_cleaner::markers::VisibilityMarkers;
+use module_structure_cleaner::orphans;
+use module_structure_cleaner::tree::ModuleTree;
+use module_structure_cleaner::{
+    diagram, export, render, CharMap, Cleaner, InvalidUtf8Error, LineEnding, RemovedEscape,
+};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
 
 /// Main entry point of the program.
 ///
 /// # Purpose
-/// This function prompts the user to select a text file, processes the file to
-/// remove ANSI escape codes and replace Unicode box-drawing characters with ASCII equivalents,
-/// and saves the cleaned output to a new file.
+/// This function cleans the input files, directories and glob patterns given on the
+/// command line, filters standard input when it is piped in, or prompts the user to
+/// select a text file when none are given and a display is available.
+/// Each file is processed to remove ANSI escape codes and replace Unicode
+/// box-drawing characters with ASCII equivalents, and the cleaned output is saved
+/// to a new file or written to standard output.
 ///
 /// # Returns
-/// - `Ok(())` if the process completes successfully.
+/// - `ExitCode::SUCCESS` if the process completes successfully.
+/// - `ExitCode::FAILURE` after printing the error if an operation fails.
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the subcommand or cleans the inputs selected by the command-line arguments.
+///
+/// # Returns
+/// - `Ok(ExitCode)` if the process completes, failing only when a check found problems.
 /// - `Err(io::Error)` if an error occurs during file operations.
-fn main() -> io::Result<()> {
-    // Prompt the user to select an input file
-    let input_file = FileDialog::new()
-        .add_filter("Text Files", &["txt"])
-        .set_title("Select Input File")
-        .pick_file()
-        .expect("No input file selected");
-
-    // Convert input file path to PathBuf
-    let input_path: PathBuf = input_file;
-
-    // Generate output file name by appending "_output" to the input file name
-    let output_file = {
-        let output_file_name = input_path
-            .file_stem()
-            .map(|stem| format!("{}_output.txt", stem.to_string_lossy()))
-            .unwrap_or_else(|| "output_output.txt".to_string());
-        input_path.with_file_name(output_file_name)
+fn run(cli: &Cli) -> io::Result<ExitCode> {
+    let cleaner = build_cleaner(cli)?;
+
+    if let Some(command) = &cli.command {
+        if !cli.inputs.is_empty() {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "input files cannot be combined with a subcommand",
+                )
+                .exit();
+        }
+        return match command {
+            Command::Diff(args) => run_diff(args, &cleaner, cli).map(|()| ExitCode::SUCCESS),
+            Command::Generate(args) => {
+                run_generate(args, &cleaner, cli).map(|()| ExitCode::SUCCESS)
+            }
+            Command::CheckOrphans(args) => run_check_orphans(args, cli),
+        };
+    }
+
+    if cli.in_place && cli.format != Format::Text {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--in-place only writes the `text` format",
+            )
+            .exit();
+    }
+    let markers = cli.markers || cli.marker_colors.is_some();
+    if markers && (cli.format != Format::Text || !cli.tree_filter().is_empty()) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--markers only applies to unfiltered `text` output",
+            )
+            .exit();
+    }
+    if cli.format == Format::Html && !cli.tree_filter().is_empty() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "the filter options do not apply to the `html` format",
+            )
+            .exit();
+    }
+
+    // Act as a filter when reading from a pipe or when `-` is the only input
+    let stdin_only = match cli.inputs.as_slice() {
+        [] => !io::stdin().is_terminal(),
+        [input] => input.as_os_str() == "-",
+        _ => false,
     };
+    if stdin_only && cli.in_place {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--in-place needs input files, not standard input",
+            )
+            .exit();
+    }
+    if stdin_only && cli.watch {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--watch needs input files, not standard input",
+            )
+            .exit();
+    }
+    if stdin_only {
+        return filter_stdin(&cleaner, cli).map(|()| ExitCode::SUCCESS);
+    }
 
-    println!(
-        "Processing file: {}\nOutput will be saved to: {}",
-        input_path.display(),
-        output_file.display()
-    );
+    let inputs = if cli.inputs.is_empty() {
+        match pick_input_file() {
+            Some(input) => vec![input],
+            None => {
+                eprintln!("No input file selected");
+                return Ok(ExitCode::SUCCESS);
+            }
+        }
+    } else {
+        cli.inputs.clone()
+    };
 
-    // Open the input file
-    let file = File::open(&input_path)?;
-    let reader = BufReader::new(file);
+    if inputs.len() > 1 && inputs.iter().any(|input| input.as_os_str() == "-") {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "standard input (`-`) cannot be combined with other inputs",
+            )
+            .exit();
+    }
 
-    // Open the output file
-    let mut output = File::create(&output_file)?;
+    let (files, unmatched) = batch::expand_inputs(&inputs, cli.recursive, &cli.suffix)?;
+    if files.len() > 1 && cli.output.is_some() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--output can only be used with a single input file",
+            )
+            .exit();
+    }
 
-    // Process each line: Remove ANSI escape codes and replace Unicode box-drawing characters
-    for line in reader.lines() {
-        let line = line?;
-        let cleaned_line = clean_text(&line);
-        writeln!(output, "{}", cleaned_line)?;
+    // A single named file keeps plain error reporting; anything else gets a summary
+    let single_file = inputs.len() == 1 && files == inputs && !cli.watch;
+    let mut summary = Summary::default();
+    for pattern in unmatched {
+        summary
+            .failed
+            .push((pattern, "no files match the pattern".to_string()));
     }
+    clean_files(&files, &cleaner, cli, &mut summary, single_file)?;
 
-    println!(
-        "Cleaning completed. Output saved to {}",
-        output_file.display()
-    );
+    if !single_file || !summary.skipped_binary.is_empty() {
+        summary.print(cli.quiet);
+    }
+    if cli.watch {
+        watch_inputs(&inputs, &files, &cleaner, cli)?;
+    }
+    match summary.failed.is_empty() {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Cleans each file and records the outcome in `summary`.
+///
+/// # Parameters
+/// - `files`: The files to clean.
+/// - `cleaner`: The configured cleaner.
+/// - `cli`: The parsed command-line arguments.
+/// - `summary`: Receives the succeeded, failed and skipped files.
+/// - `fail_fast`: Return the first error instead of recording it.
+///
+/// # Details
+/// - Files that look binary are skipped, see [`batch::is_binary`].
+fn clean_files(
+    files: &[PathBuf],
+    cleaner: &Cleaner,
+    cli: &Cli,
+    summary: &mut Summary,
+    fail_fast: bool,
+) -> io::Result<()> {
+    for input_path in files {
+        let result =
+            batch::is_binary(input_path, cli.input_encoding).and_then(|binary| match binary {
+                true => Ok(false),
+                false => process_file(input_path, cleaner, cli).map(|()| true),
+            });
+        match result {
+            Ok(true) => summary.succeeded.push(input_path.clone()),
+            Ok(false) => summary.skipped_binary.push(input_path.clone()),
+            Err(error) if fail_fast => {
+                return Err(with_source(error, &input_path.display().to_string()))
+            }
+            Err(error) => summary.failed.push((input_path.clone(), error.to_string())),
+        }
+    }
     Ok(())
 }
 
-/// Cleans text by removing ANSI escape codes and replacing Unicode box-drawing characters.
+/// Cleans files again whenever they change, until the process is terminated.
 ///
 /// # Parameters
-/// - `input`: A string slice representing a single line of text to be cleaned.
+/// - `inputs`: The inputs from the command line; directories are watched as a whole.
+/// - `files`: The files expanded from the inputs; those outside the watched
+///   directories are watched one by one.
+/// - `cleaner`: The configured cleaner.
+/// - `cli`: The parsed command-line arguments.
 ///
 /// # Returns
-/// - A `String` containing the cleaned text.
+/// - `Err(io::Error)` if the inputs cannot be watched.
 ///
 /// # Details
-/// - ANSI escape codes are removed using a regex.
-/// - Unicode box-drawing characters are replaced with their ASCII equivalents.
-fn clean_text(input: &str) -> String {
-    // Remove ANSI escape codes using a regex
-    let ansi_regex = regex::Regex::new(r"\x1B\[[0-9;]*[a-zA-Z]").unwrap();
-    let no_ansi = ansi_regex.replace_all(input, "");
-
-    // Replace Unicode box-drawing characters with ASCII equivalents
-    no_ansi
-        .replace('├', "+")
-        .replace('─', "-")
-        .replace('│', "|")
-        .replace(['└', '┌', '┐', '┘', '┬', '┴', '┼', '╭', '╮', '╯', '╰'], "+")
-        .replace('╱', "/")
-        .replace('╲', "\\")
-        .replace('╳', "X")
-        .replace('╴', "-")
-        .replace('╵', "|")
-        .replace('╶', "-")
-        .replace('╷', "|")
-        .replace('╸', "-")
-        .replace('╹', "|")
-        .replace('╺', "-")
-        .replace('╻', "|")
-        .replace('╼', "-")
-        .replace('╽', "|")
-        .replace('╾', "-")
-        .replace('╿', "|")
-        .replace('═', "=")
-        .replace('║', "|")
-        .replace(
-            [
-                '╒', '╓', '╔', '╕', '╖', '╗', '╘', '╙', '╚', '╛', '╜', '╝', '╞', '╟', '╠', '╡',
-                '╢', '╣', '╤', '╥', '╦', '╧', '╨', '╩', '╪', '╫', '╬', '╭', '╮', '╯', '╰',
-            ],
-            "+",
+/// - Changes to the tool's own files are ignored to avoid feedback loops: outputs
+///   named with the suffix, `--output`, backups and hidden temporary files. Files
+///   rewritten in place keep their modification time and are not seen as changed.
+/// - Each rebuild prints one status line with its totals.
+fn watch_inputs(
+    inputs: &[PathBuf],
+    files: &[PathBuf],
+    cleaner: &Cleaner,
+    cli: &Cli,
+) -> io::Result<()> {
+    let dirs: Vec<&PathBuf> = inputs.iter().filter(|input| input.is_dir()).collect();
+    let mut targets: Vec<PathBuf> = dirs.iter().map(|dir| dir.to_path_buf()).collect();
+    targets.extend(
+        files
+            .iter()
+            .filter(|file| !dirs.iter().any(|dir| file.starts_with(dir)))
+            .cloned(),
+    );
+
+    let output = cli
+        .output
+        .as_ref()
+        .and_then(|output| fs::canonicalize(output).ok());
+    let is_own_file = |path: &Path| {
+        let name = path.file_name().map(|name| name.to_string_lossy());
+        name.as_ref().is_some_and(|name| name.starts_with('.'))
+            || batch::is_output(path, &cli.suffix)
+            || output.as_deref() == Some(path)
+            || cli
+                .backup_suffix
+                .as_ref()
+                .zip(name.as_ref())
+                .is_some_and(|(suffix, name)| name.ends_with(suffix.as_str()))
+    };
+
+    let rebuild_cli = Cli {
+        quiet: true,
+        ..cli.clone()
+    };
+    if !cli.quiet {
+        eprintln!("Watching for changes, press Ctrl-C to stop");
+    }
+    watch::watch(&targets, files, cli.recursive, |changed| {
+        let changed: Vec<PathBuf> = changed
+            .into_iter()
+            .filter(|path| !is_own_file(path))
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        let started = Instant::now();
+        let mut summary = Summary::default();
+        let _ = clean_files(&changed, cleaner, &rebuild_cli, &mut summary, false);
+        for (path, error) in &summary.failed {
+            eprintln!("failed: {}: {}", path.display(), error);
+        }
+        if !cli.quiet || !summary.failed.is_empty() {
+            eprintln!(
+                "Rebuilt {} file(s) in {} ms: {}",
+                changed.len(),
+                started.elapsed().as_millis(),
+                summary.totals()
+            );
+        }
+    })
+}
+
+/// Prompts the user to select an input file with the native file dialog.
+///
+/// # Returns
+/// - `Some(PathBuf)` with the selected file.
+/// - `None` if the user cancelled the dialog.
+///
+/// # Details
+/// - Exits with a usage error when no display is available or the binary was
+///   built without the `gui` feature, so headless runs never block on a dialog.
+fn pick_input_file() -> Option<PathBuf> {
+    #[cfg(feature = "gui")]
+    if cli::display_available() {
+        return rfd::FileDialog::new()
+            .add_filter("Text Files", &["txt"])
+            .set_title("Select Input File")
+            .pick_file();
+    }
+
+    Cli::command()
+        .error(
+            ErrorKind::MissingRequiredArgument,
+            "no input files given and no display available for the file dialog",
         )
-        .replace('╱', "/")
-        .replace('╲', "\\")
-        .replace('╳', "X")
-        .replace('╴', "-")
-        .replace('╵', "|")
-        .replace('╶', "-")
-        .replace('╷', "|")
-        .replace('╸', "-")
-        .replace('╹', "|")
-        .replace('╺', "-")
-        .replace('╻', "|")
-        .replace('╼', "-")
-        .replace('╽', "|")
-        .replace('╾', "-")
-        .replace('╿', "|")
-        .to_string()
+        .exit()
+}
+
+/// Creates the cleaner configured by the command-line arguments.
+///
+/// # Parameters
+/// - `cli`: The parsed command-line arguments.
+///
+/// # Returns
+/// - `Ok(Cleaner)` with the selected preset, mapping file and marker colors applied.
+/// - `Err(io::Error)` if the mapping file or the marker file cannot be read or is invalid.
+fn build_cleaner(cli: &Cli) -> io::Result<Cleaner> {
+    let markers = match &cli.marker_colors {
+        Some(marker_file) => Some(VisibilityMarkers::load(marker_file)?),
+        None => cli.markers.then(VisibilityMarkers::new),
+    };
+    let mapping = match &cli.map {
+        Some(map_file) => MappingFile::load(map_file)?.to_char_map(cli.preset.as_deref())?,
+        None => CharMap::preset(cli.preset.as_deref().unwrap_or(PRESETS[0]))
+            .expect("preset names are validated by the argument parser"),
+    };
+    Ok(Cleaner::new()
+        .mapping(mapping)
+        .line_ending(cli.eol.map(Into::into))
+        .strip_escapes(!cli.keep_escapes)
+        .transliterate(!cli.keep_boxes)
+        .lossy(cli.lossy.clone())
+        .visibility_markers(markers))
+}
+
+/// Cleans standard input and streams the result to standard output or `--output`.
+///
+/// # Parameters
+/// - `cleaner`: The configured cleaner.
+/// - `cli`: The parsed command-line arguments selecting the output destination.
+///
+/// # Returns
+/// - `Ok(())` once standard input is exhausted.
+/// - `Err(io::Error)` if reading or writing fails.
+///
+/// # Details
+/// - Output is flushed after every line so the filter keeps up with long-running
+///   producers such as `cargo modules structure`.
+fn filter_stdin(cleaner: &Cleaner, cli: &Cli) -> io::Result<()> {
+    let cleaner = cleaner.clone().flush_lines(true);
+    let reader = io::stdin().lock();
+    let report = escape_reporter("<stdin>".to_string(), cli);
+
+    let result = match &cli.output {
+        Some(output_file) => transform(&cleaner, reader, File::create(output_file)?, report, cli),
+        None => transform(&cleaner, reader, io::stdout().lock(), report, cli),
+    };
+    result.map_err(|error| with_source(error, "<stdin>"))
+}
+
+/// Names the input in an [`InvalidUtf8Error`], which only knows its position.
+///
+/// # Details
+/// - Other errors are returned unchanged.
+fn with_source(error: io::Error, source: &str) -> io::Error {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<InvalidUtf8Error>())
+    {
+        Some(invalid) => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{}: {}; use --lossy to replace it, or --input-encoding if the input is not UTF-8",
+                source, invalid
+            ),
+        ),
+        None => error,
+    }
+}
+
+/// Cleans a single input file and writes the result to its output destination.
+///
+/// # Parameters
+/// - `input_path`: The file to clean.
+/// - `cleaner`: The configured cleaner.
+/// - `cli`: The parsed command-line arguments selecting the output destination.
+///
+/// # Returns
+/// - `Ok(())` if the file was cleaned successfully.
+/// - `Err(io::Error)` if reading the input or writing the output fails.
+fn process_file(input_path: &Path, cleaner: &Cleaner, cli: &Cli) -> io::Result<()> {
+    let report = escape_reporter(input_path.display().to_string(), cli);
+
+    if cli.stdout {
+        let reader = BufReader::new(File::open(input_path)?);
+        return transform(cleaner, reader, io::stdout().lock(), report, cli);
+    }
+
+    if cli.in_place {
+        if !cli.quiet {
+            eprintln!("Cleaning in place: {}", input_path.display());
+        }
+        return in_place::rewrite(input_path, cli.backup_suffix.as_deref(), |writer| {
+            let reader = BufReader::new(File::open(input_path)?);
+            transform(cleaner, reader, writer, report, cli)
+        });
+    }
+
+    // Generate output file name by appending the suffix to the input file name
+    let extension = cli.format.extension();
+    let output_file = match &cli.output {
+        Some(output) => output.clone(),
+        None => {
+            let output_file_name = input_path
+                .file_stem()
+                .map(|stem| format!("{}{}.{}", stem.to_string_lossy(), cli.suffix, extension))
+                .unwrap_or_else(|| format!("output{}.{}", cli.suffix, extension));
+            input_path.with_file_name(output_file_name)
+        }
+    };
+
+    if !cli.quiet {
+        eprintln!(
+            "Processing file: {}\nOutput will be saved to: {}",
+            input_path.display(),
+            output_file.display()
+        );
+    }
+
+    let reader = BufReader::new(File::open(input_path)?);
+    let writer = BufWriter::new(File::create(&output_file)?);
+    transform(cleaner, reader, writer, report, cli)?;
+
+    if !cli.quiet {
+        eprintln!(
+            "Cleaning completed. Output saved to {}",
+            output_file.display()
+        );
+    }
+    Ok(())
+}
+
+/// Compares two module-structure snapshots and writes the diff.
+///
+/// # Parameters
+/// - `args`: The arguments of the `diff` subcommand.
+/// - `cleaner`: The configured cleaner, used to draw the annotated tree in ASCII.
+/// - `cli`: The parsed command-line arguments selecting the input encoding.
+///
+/// # Returns
+/// - `Ok(())` if the diff was written.
+/// - `Err(io::Error)` if reading or parsing a snapshot or writing the diff fails.
+fn run_diff(args: &DiffArgs, cleaner: &Cleaner, cli: &Cli) -> io::Result<()> {
+    let read_snapshot = |path: &Path| {
+        let reader = BufReader::new(File::open(path)?);
+        ModuleTree::from_reader(encoding::decoding_reader(reader, cli.input_encoding)?)
+    };
+    let old = read_snapshot(&args.old)?;
+    let new = read_snapshot(&args.new)?;
+    let diff = TreeDiff::new(&old, &new);
+
+    let rendered = match args.format {
+        DiffFormat::Tree => cleaner.clean_str(&diff.to_annotated_tree()),
+        DiffFormat::Unified => diff.to_unified(
+            &args.old.display().to_string(),
+            &args.new.display().to_string(),
+        ),
+        DiffFormat::Json => diff.to_json()?,
+    };
+
+    match &args.output {
+        Some(output_file) => std::fs::write(output_file, rendered),
+        None => io::stdout().lock().write_all(rendered.as_bytes()),
+    }
+}
+
+/// Runs `cargo modules structure`, or the native extractor, and writes the cleaned output.
+///
+/// # Parameters
+/// - `args`: The arguments of the `generate` subcommand.
+/// - `cleaner`: The configured cleaner.
+/// - `cli`: The parsed command-line arguments selecting the output format.
+///
+/// # Returns
+/// - `Ok(())` if the output was written.
+/// - `Err(io::Error)` if cargo-modules is missing or fails, a source file cannot be
+///   parsed, or writing fails.
+fn run_generate(args: &GenerateArgs, cleaner: &Cleaner, cli: &Cli) -> io::Result<()> {
+    let structure = if args.native {
+        render::to_text(&extract::extract(&args.path, &args.structure_options())?.tree)
+    } else {
+        cargo_modules::structure(&args.path, &args.structure_options())?
+    };
+    let report = escape_reporter("cargo modules structure".to_string(), cli);
+
+    match &args.output {
+        Some(output_file) => {
+            let writer = BufWriter::new(File::create(output_file)?);
+            transform(cleaner, structure.as_bytes(), writer, report, cli)
+        }
+        None => transform(
+            cleaner,
+            structure.as_bytes(),
+            io::stdout().lock(),
+            report,
+            cli,
+        ),
+    }
+}
+
+/// Compares the module trees of a crate with its source files and prints the problems.
+///
+/// # Parameters
+/// - `args`: The arguments of the `check-orphans` subcommand.
+/// - `cli`: The parsed command-line arguments.
+///
+/// # Returns
+/// - `Ok(ExitCode::SUCCESS)` if every file is reached and every module file exists.
+/// - `Ok(ExitCode::FAILURE)` if orphaned files or missing module files were found.
+/// - `Err(io::Error)` if the sources cannot be read or parsed.
+fn run_check_orphans(args: &CheckOrphansArgs, cli: &Cli) -> io::Result<ExitCode> {
+    let report = orphans::check(&args.path)?;
+    let mut stdout = io::stdout().lock();
+
+    for orphan in &report.orphans {
+        writeln!(stdout, "orphan: {}", orphan.display())?;
+    }
+    for missing in &report.missing {
+        let candidates: Vec<String> = missing
+            .candidates
+            .iter()
+            .map(|candidate| candidate.display().to_string())
+            .collect();
+        writeln!(
+            stdout,
+            "missing: {} declared in {} (expected {})",
+            missing.path,
+            missing.declared_in.display(),
+            candidates.join(" or ")
+        )?;
+    }
+
+    if report.is_clean() {
+        if !cli.quiet {
+            eprintln!("No orphaned files or missing modules found");
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    if !cli.quiet {
+        eprintln!(
+            "{} orphaned file(s), {} missing module file(s)",
+            report.orphans.len(),
+            report.missing.len()
+        );
+    }
+    Ok(ExitCode::FAILURE)
+}
+
+/// Cleans the text read from `reader`, or exports its module tree, into `writer`.
+///
+/// # Parameters
+/// - `cleaner`: The configured cleaner.
+/// - `reader`: The source of the text.
+/// - `writer`: The destination of the output.
+/// - `report`: Called with each removed escape sequence when cleaning text.
+/// - `cli`: The parsed command-line arguments selecting the output format.
+///
+/// # Returns
+/// - `Ok(())` if the output was written.
+/// - `Err(io::Error)` if reading, parsing or writing fails.
+///
+/// # Details
+/// - Text output is streamed line by line. Every other format, and text output with
+///   a tree filter, reads the whole input and parses it into a [`ModuleTree`] first.
+///   HTML output converts the whole input with its colors instead.
+/// - Line endings follow `--eol`. Otherwise streamed text keeps those of each input
+///   line, and rendered text uses the line ending of the first input line.
+/// - Input is decoded from `--input-encoding` or the detected encoding, and output
+///   is encoded to `--output-encoding`. UTF-8 output starts with a byte order mark if
+///   the input starts with a UTF-8 one, so rewriting such a file keeps its bytes.
+fn transform(
+    cleaner: &Cleaner,
+    mut reader: impl BufRead,
+    writer: impl Write,
+    report: impl FnMut(RemovedEscape),
+    cli: &Cli,
+) -> io::Result<()> {
+    let utf8_bom = encoding::starts_with_utf8_bom(&mut reader)?;
+    let reader = encoding::decoding_reader(reader, cli.input_encoding)?;
+    let output_encoding = cli.output_encoding.unwrap_or(encoding_rs::UTF_8);
+    let mut writer = encoding::encoding_writer(writer, output_encoding, utf8_bom);
+    let filter = cli.tree_filter();
+    if cli.format == Format::Text && filter.is_empty() {
+        return cleaner.clean_reader_to_writer_with_report(reader, writer, report);
+    }
+
+    let input = cleaner.read_text(reader)?;
+    let tree = || ModuleTree::parse(&input).map(|tree| filter.apply(&tree));
+    let rendered = match cli.format {
+        Format::Text => {
+            let line_ending = match cli.eol {
+                Some(eol) => eol.into(),
+                None => LineEnding::detect(&input).unwrap_or(LineEnding::Lf),
+            };
+            let ellipsis = match cli.keep_boxes {
+                true => render::ELLIPSIS,
+                false => render::ASCII_ELLIPSIS,
+            };
+            let text = render::to_text_with_ellipsis(&tree()?, ellipsis);
+            line_ending.apply(&cleaner.clean_str(&text))
+        }
+        Format::Json => export::to_json(&tree()?)?,
+        Format::Yaml => export::to_yaml(&tree()?)?,
+        Format::Dot => diagram::to_dot(&tree()?, &cli.diagram_options()),
+        Format::Mermaid => diagram::to_mermaid_graph(&tree()?, &cli.diagram_options()),
+        Format::MermaidMindmap => diagram::to_mermaid_mindmap(&tree()?, &cli.diagram_options()),
+        Format::Plantuml => diagram::to_plantuml(&tree()?, &cli.diagram_options()),
+        Format::Html => html::to_html(
+            &input,
+            cli.html_ascii.then_some(cleaner),
+            &cli.html_options(),
+        ),
+    };
+    let rendered = match (cli.format, cli.eol) {
+        (Format::Text, _) | (_, None) => rendered,
+        (_, Some(eol)) => LineEnding::from(eol).apply(&rendered),
+    };
+    writer.write_all(rendered.as_bytes())?;
+    writer.flush()
+}
+
+/// Creates the callback that prints removed escape sequences for `--report-escapes`.
+///
+/// # Parameters
+/// - `source`: The name of the input shown in front of each report line.
+/// - `cli`: The parsed command-line arguments.
+///
+/// # Returns
+/// - A callback that prints `source:line:column: kind "raw"` to standard error, or
+///   does nothing when reporting is disabled.
+fn escape_reporter(source: String, cli: &Cli) -> impl FnMut(RemovedEscape) {
+    let enabled = cli.report_escapes;
+    move |escape| {
+        if enabled {
+            eprintln!(
+                "{}:{}:{}: removed {}{} {:?}",
+                source,
+                escape.line,
+                escape.column,
+                escape.kind,
+                if escape.terminated {
+                    ""
+                } else {
+                    " (unterminated)"
+                },
+                escape.raw
+            );
+        }
+    }
 }
@@ -0,0 +1,459 @@
+//! Character mapping tables that decide how box-drawing characters are rendered.
+//!
+//! A [`CharMap`] starts from one of the named [`PRESETS`] and can be overridden or
+//! extended per character, either in code or from a TOML mapping file:
+//!
+//! ```toml
+//! # Optional preset to start from, `plus` when omitted
+//! preset = "tree-classic"
+//!
+//! [map]
+//! "└" = "`"
+//! "U+2502" = "|"
+//! "U+2580-U+259F" = "#"
+//! "→" = "->"
+//! ```
+
+use crate::box_drawing;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Names of the built-in presets, the first one being the default.
+///
+/// - `plus`: the built-in table, corners and junctions drawn as `+` (`+-- item`).
+/// - `tree-classic`: the style of the classic `tree` command (`|-- item`, `` `-- item``).
+/// - `markdown-safe`: avoids the Markdown table separator `|`, the heading marker `#`
+///   and the emphasis marker `_`, so the output can be pasted into Markdown as is.
+pub const PRESETS: &[&str] = &["plus", "tree-classic", "markdown-safe"];
+
+/// Maps characters to their replacement text.
+///
+/// # Details
+/// - Characters without an override use the [`box_drawing`] table.
+/// - Overrides may map any character, not just box-drawing characters, and may map
+///   a character to an empty string to remove it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharMap {
+    overrides: HashMap<char, String>,
+}
+
+impl CharMap {
+    /// Creates a map that uses the built-in table, the same as the `plus` preset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the map of a named preset.
+    ///
+    /// # Parameters
+    /// - `name`: One of the names in [`PRESETS`].
+    ///
+    /// # Returns
+    /// - `Some(CharMap)` for a known preset.
+    /// - `None` if no preset has that name.
+    pub fn preset(name: &str) -> Option<Self> {
+        let mut map = Self::new();
+        match name {
+            "plus" => {}
+            "tree-classic" => {
+                // Last children close with a backtick, other children hang off a pipe
+                for c in "└┕┖┗╘╙╚╰".chars() {
+                    map.set(c, "`");
+                }
+                for c in "├┝┞┟┠┡┢┣╞╟╠".chars() {
+                    map.set(c, "|");
+                }
+            }
+            "markdown-safe" => {
+                for (index, replacement) in box_drawing::ASCII_TABLE.iter().enumerate() {
+                    let markdown_safe = match *replacement {
+                        "|" => ":",
+                        "#" => "=",
+                        "_" => ".",
+                        _ => continue,
+                    };
+                    let c = char::from_u32(box_drawing::FIRST as u32 + index as u32).unwrap();
+                    map.set(c, markdown_safe);
+                }
+            }
+            _ => return None,
+        }
+        Some(map)
+    }
+
+    /// Parses a TOML mapping file.
+    ///
+    /// # Parameters
+    /// - `toml`: The contents of the mapping file.
+    ///
+    /// # Returns
+    /// - `Ok(CharMap)` with the file's preset and entries applied.
+    /// - `Err(io::Error)` of kind `InvalidData` if the file is malformed, names an
+    ///   unknown preset or contains an invalid character key.
+    pub fn from_toml_str(toml: &str) -> io::Result<Self> {
+        MappingFile::from_toml_str(toml)?.to_char_map(None)
+    }
+
+    /// Loads a TOML mapping file from disk.
+    ///
+    /// # Parameters
+    /// - `path`: The mapping file to read.
+    ///
+    /// # Returns
+    /// - `Ok(CharMap)` with the file's preset and entries applied.
+    /// - `Err(io::Error)` if the file cannot be read or is invalid.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Applies the entries of a mapping file on top of this map.
+    ///
+    /// # Parameters
+    /// - `file`: The parsed mapping file. Its `preset` key is ignored.
+    ///
+    /// # Returns
+    /// - `Ok(())` if every key was valid.
+    /// - `Err(io::Error)` of kind `InvalidData` for the first invalid key.
+    pub fn apply(&mut self, file: &MappingFile) -> io::Result<()> {
+        for (key, replacement) in &file.map {
+            for c in parse_key(key)? {
+                self.set(c, replacement.as_str());
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides the replacement of a single character.
+    pub fn set(&mut self, c: char, replacement: impl Into<String>) {
+        self.overrides.insert(c, replacement.into());
+    }
+
+    /// Returns the replacement of `c`, or `None` if `c` is kept unchanged.
+    pub fn replacement(&self, c: char) -> Option<&str> {
+        match self.overrides.get(&c) {
+            Some(replacement) => Some(replacement),
+            None => box_drawing::ascii_replacement(c),
+        }
+    }
+
+    /// Returns the overridden characters and their replacements.
+    pub fn overrides(&self) -> impl Iterator<Item = (char, &str)> {
+        self.overrides
+            .iter()
+            .map(|(&c, replacement)| (c, replacement.as_str()))
+    }
+
+    /// Compiles the map into the lookup table used by the cleaning engine.
+    pub(crate) fn compile(&self) -> CompiledMap {
+        let box_table = (box_drawing::FIRST..=box_drawing::LAST)
+            .map(|c| self.replacement(c).unwrap_or_default().into())
+            .collect();
+
+        let mut ascii_mapped = [false; 128];
+        let mut others = HashMap::new();
+        for (c, replacement) in self.overrides() {
+            if (box_drawing::FIRST..=box_drawing::LAST).contains(&c) {
+                continue;
+            }
+            if c.is_ascii() {
+                ascii_mapped[c as usize] = true;
+            }
+            others.insert(c, replacement.into());
+        }
+
+        CompiledMap {
+            ascii_mapped,
+            box_table,
+            others,
+        }
+    }
+}
+
+/// A [`CharMap`] resolved into flat lookup tables.
+///
+/// # Details
+/// - Box-drawing and block element characters are looked up by index.
+/// - Runs of ASCII characters without an override are copied unchanged.
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledMap {
+    /// `true` for each ASCII character that has an override.
+    ascii_mapped: [bool; 128],
+    /// Replacement for each code point from `box_drawing::FIRST` to `box_drawing::LAST`.
+    box_table: Box<[Box<str>]>,
+    /// Replacements for every other overridden character.
+    others: HashMap<char, Box<str>>,
+}
+
+impl Default for CompiledMap {
+    fn default() -> Self {
+        CharMap::new().compile()
+    }
+}
+
+impl CompiledMap {
+    /// Appends `text` to `output`, replacing every mapped character.
+    ///
+    /// # Parameters
+    /// - `text`: Text that contains no escape sequences.
+    /// - `output`: The buffer that receives the transliterated text.
+    pub(crate) fn transliterate_into(&self, text: &str, output: &mut String) {
+        let bytes = text.as_bytes();
+        let mut start = 0;
+
+        while start < bytes.len() {
+            // Fast path: copy the run of unmapped ASCII characters in one go
+            let run = bytes[start..]
+                .iter()
+                .position(|&byte| !byte.is_ascii() || self.ascii_mapped[byte as usize])
+                .map_or(bytes.len(), |offset| start + offset);
+            output.push_str(&text[start..run]);
+            if run == bytes.len() {
+                break;
+            }
+
+            let c = text[run..].chars().next().unwrap();
+            match self.lookup(c) {
+                Some(replacement) => output.push_str(replacement),
+                None => output.push(c),
+            }
+            start = run + c.len_utf8();
+        }
+    }
+
+    /// Returns the replacement of `c`, or `None` if `c` is kept unchanged.
+    fn lookup(&self, c: char) -> Option<&str> {
+        if (box_drawing::FIRST..=box_drawing::LAST).contains(&c) {
+            return Some(&self.box_table[c as usize - box_drawing::FIRST as usize]);
+        }
+        self.others.get(&c).map(|replacement| &**replacement)
+    }
+}
+
+/// The contents of a TOML mapping file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MappingFile {
+    /// The preset the file builds on.
+    pub preset: Option<String>,
+    /// Replacements keyed by a character, a `U+XXXX` code point or a `U+XXXX-U+YYYY` range.
+    #[serde(default)]
+    pub map: BTreeMap<String, String>,
+}
+
+impl MappingFile {
+    /// Parses the contents of a TOML mapping file without applying it.
+    ///
+    /// # Returns
+    /// - `Ok(MappingFile)` if the contents are valid TOML with the expected keys.
+    /// - `Err(io::Error)` of kind `InvalidData` otherwise.
+    pub fn from_toml_str(toml: &str) -> io::Result<Self> {
+        toml::from_str(toml).map_err(|error| invalid_data(error.to_string()))
+    }
+
+    /// Reads and parses a TOML mapping file without applying it.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Builds the character map described by this file.
+    ///
+    /// # Parameters
+    /// - `preset`: The preset to start from instead of the file's own `preset` key.
+    ///
+    /// # Returns
+    /// - `Ok(CharMap)` with the preset and the file's entries applied.
+    /// - `Err(io::Error)` of kind `InvalidData` for an unknown preset or an invalid key.
+    pub fn to_char_map(&self, preset: Option<&str>) -> io::Result<CharMap> {
+        let mut map = match preset.or(self.preset.as_deref()) {
+            Some(name) => CharMap::preset(name).ok_or_else(|| unknown_preset(name))?,
+            None => CharMap::new(),
+        };
+        map.apply(self)?;
+        Ok(map)
+    }
+}
+
+/// Parses a mapping key into the characters it covers.
+///
+/// # Parameters
+/// - `key`: A single character, a `U+XXXX` code point or a `U+XXXX-U+YYYY` range.
+///
+/// # Returns
+/// - `Ok(Vec<char>)` with every covered character.
+/// - `Err(io::Error)` of kind `InvalidData` if the key is malformed.
+fn parse_key(key: &str) -> io::Result<Vec<char>> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(vec![c]);
+    }
+
+    let invalid = || invalid_data(format!("invalid mapping key {:?}", key));
+    let code_point = |text: &str| {
+        text.strip_prefix("U+")
+            .or_else(|| text.strip_prefix("u+"))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32)
+            .ok_or_else(invalid)
+    };
+
+    match key.split_once('-') {
+        Some((first, last)) => {
+            let (first, last) = (code_point(first)?, code_point(last)?);
+            if first > last {
+                return Err(invalid());
+            }
+            Ok((first..=last).collect())
+        }
+        None => Ok(vec![code_point(key)?]),
+    }
+}
+
+/// Creates the error returned for a preset name that is not in [`PRESETS`].
+fn unknown_preset(name: &str) -> io::Error {
+    invalid_data(format!(
+        "unknown preset {:?}, expected one of: {}",
+        name,
+        PRESETS.join(", ")
+    ))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn box_chars() -> impl Iterator<Item = char> {
+        box_drawing::FIRST..=box_drawing::LAST
+    }
+
+    #[test]
+    fn keys_accept_characters_code_points_and_ranges() {
+        assert_eq!(parse_key("│").unwrap(), ['│']);
+        assert_eq!(parse_key("U+2502").unwrap(), ['│']);
+        assert_eq!(parse_key("u+2502").unwrap(), ['│']);
+        assert_eq!(parse_key("U+2500-U+2503").unwrap(), ['─', '━', '│', '┃']);
+        assert_eq!(parse_key("U+2502-U+2502").unwrap(), ['│']);
+        // A lone `-` is a character, not a range
+        assert_eq!(parse_key("-").unwrap(), ['-']);
+    }
+
+    #[test]
+    fn malformed_keys_are_rejected() {
+        for key in [
+            "",
+            "ab",
+            "U+",
+            "U+XYZ",
+            "2502",
+            "U+D800",
+            "U+110000",
+            "U+2503-U+2500",
+            "U+2500-",
+            "U+2500-2503",
+        ] {
+            let error = parse_key(key).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn toml_escapes_and_ranges_are_applied() {
+        let map = CharMap::from_toml_str(
+            "[map]\n\
+             \"\\u2502\" = \"!\"\n\
+             \"U+2580-U+2581\" = \"#\"\n\
+             \"→\" = \"->\"\n\
+             \"x\" = \"\"\n",
+        )
+        .unwrap();
+        assert_eq!(map.replacement('│'), Some("!"));
+        assert_eq!(map.replacement('▀'), Some("#"));
+        assert_eq!(map.replacement('▁'), Some("#"));
+        assert_eq!(map.replacement('→'), Some("->"));
+        assert_eq!(map.replacement('x'), Some(""));
+        assert_eq!(map.replacement('─'), Some("-"));
+        assert_eq!(map.replacement('é'), None);
+
+        let mut output = String::new();
+        map.compile().transliterate_into("x│→é ├─", &mut output);
+        assert_eq!(output, "!->é +-");
+    }
+
+    #[test]
+    fn file_preset_is_applied_before_the_entries() {
+        let map =
+            CharMap::from_toml_str("preset = \"tree-classic\"\n[map]\n\"├\" = \"+\"\n").unwrap();
+        assert_eq!(map.replacement('└'), Some("`"));
+        assert_eq!(map.replacement('├'), Some("+"));
+
+        // A preset given on the command line wins over the file's
+        let file = MappingFile::from_toml_str("preset = \"tree-classic\"\n").unwrap();
+        let map = file.to_char_map(Some("markdown-safe")).unwrap();
+        assert_eq!(map.replacement('└'), Some("+"));
+        assert_eq!(map.replacement('│'), Some(":"));
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        for toml in [
+            "[map\n",
+            "[map]\n\"│\" = 1\n",
+            "[mapping]\n\"│\" = \"|\"\n",
+            "preset = \"fancy\"\n",
+            "[map]\n\"ab\" = \"|\"\n",
+        ] {
+            let error = CharMap::from_toml_str(toml).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{:?}", toml);
+        }
+        let error = CharMap::from_toml_str("preset = \"fancy\"\n").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("plus, tree-classic, markdown-safe"));
+    }
+
+    #[test]
+    fn plus_preset_is_the_built_in_table() {
+        let plus = CharMap::preset("plus").unwrap();
+        assert_eq!(plus, CharMap::new());
+        for c in box_chars() {
+            assert_eq!(plus.replacement(c), box_drawing::ascii_replacement(c));
+        }
+        assert_eq!(CharMap::preset("fancy"), None);
+    }
+
+    #[test]
+    fn tree_classic_preset_changes_only_corners_and_tees() {
+        let classic = CharMap::preset("tree-classic").unwrap();
+        for c in box_chars() {
+            let expected = match c {
+                '└' | '┕' | '┖' | '┗' | '╘' | '╙' | '╚' | '╰' => Some("`"),
+                '├' | '┝' | '┞' | '┟' | '┠' | '┡' | '┢' | '┣' | '╞' | '╟' | '╠' => {
+                    Some("|")
+                }
+                c => box_drawing::ascii_replacement(c),
+            };
+            assert_eq!(classic.replacement(c), expected, "{}", c);
+        }
+    }
+
+    #[test]
+    fn markdown_safe_preset_avoids_markdown_syntax() {
+        let markdown = CharMap::preset("markdown-safe").unwrap();
+        for c in box_chars() {
+            let replacement = markdown.replacement(c).unwrap();
+            let expected = match box_drawing::ascii_replacement(c).unwrap() {
+                "|" => ":",
+                "#" => "=",
+                "_" => ".",
+                other => other,
+            };
+            assert_eq!(replacement, expected, "{}", c);
+        }
+    }
+}
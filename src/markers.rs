@@ -0,0 +1,231 @@
+//! Visibility markers inferred from the colors of `cargo modules structure` output.
+//!
+//! cargo-modules colors items by visibility, and the colors are lost when escape
+//! sequences are removed. [`VisibilityMarkers`] maps colors back to markers such as
+//! `pub` or `pub(crate)` that are appended to the cleaned line as `[pub(crate)]`.
+//!
+//! The colors can be changed with a TOML file for other cargo-modules versions and
+//! themes:
+//!
+//! ```toml
+//! # Start from an empty table instead of the built-in colors
+//! defaults = false
+//!
+//! [colors]
+//! green = "pub"
+//! yellow = "pub(crate)"
+//! "#fe6d00" = "pub(super)"
+//! red = ""  # An empty marker removes the color
+//! ```
+
+use crate::ansi::{self, Token};
+use crate::sgr::{Color, Style};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Maps foreground colors to the visibility markers they stand for.
+///
+/// # Details
+/// - The default table follows the cargo-modules theme: green `pub`, yellow
+///   `pub(crate)`, orange (`#fe6d00`) `pub(in path)`, red `pub(self)` and magenta
+///   `orphan`.
+/// - Colors are compared by their RGB value, so `#00cd00` and `green` are the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisibilityMarkers {
+    colors: Vec<(Color, String)>,
+}
+
+impl Default for VisibilityMarkers {
+    fn default() -> Self {
+        let mut markers = Self::empty();
+        markers.set(Color::Indexed(2), "pub");
+        markers.set(Color::Indexed(3), "pub(crate)");
+        markers.set(Color::Rgb(254, 109, 0), "pub(in path)");
+        markers.set(Color::Indexed(1), "pub(self)");
+        markers.set(Color::Indexed(5), "orphan");
+        markers
+    }
+}
+
+impl VisibilityMarkers {
+    /// Creates the default table of the cargo-modules theme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a table without any colors.
+    pub fn empty() -> Self {
+        Self { colors: Vec::new() }
+    }
+
+    /// Sets the marker of a color, replacing any previous marker of that color.
+    pub fn set(&mut self, color: Color, marker: impl Into<String>) {
+        self.remove(color);
+        self.colors.push((color, marker.into()));
+    }
+
+    /// Removes the marker of a color.
+    pub fn remove(&mut self, color: Color) {
+        self.colors
+            .retain(|(candidate, _)| candidate.to_rgb() != color.to_rgb());
+    }
+
+    /// Returns the marker of a color, if it has one.
+    pub fn get(&self, color: Color) -> Option<&str> {
+        self.colors
+            .iter()
+            .find(|(candidate, _)| candidate.to_rgb() == color.to_rgb())
+            .map(|(_, marker)| marker.as_str())
+    }
+
+    /// Parses a TOML marker file.
+    ///
+    /// # Returns
+    /// - `Ok(VisibilityMarkers)` with the file's colors applied to the defaults, or to
+    ///   an empty table if the file sets `defaults = false`.
+    /// - `Err(io::Error)` of kind `InvalidData` if the file is malformed or contains an
+    ///   invalid color.
+    pub fn from_toml_str(toml: &str) -> io::Result<Self> {
+        let file: MarkerFile =
+            toml::from_str(toml).map_err(|error| invalid_data(error.to_string()))?;
+        let mut markers = match file.defaults {
+            true => Self::new(),
+            false => Self::empty(),
+        };
+        for (key, marker) in file.colors {
+            let color = Color::parse(&key)
+                .ok_or_else(|| invalid_data(format!("invalid color {:?}", key)))?;
+            match marker.is_empty() {
+                true => markers.remove(color),
+                false => markers.set(color, marker),
+            }
+        }
+        Ok(markers)
+    }
+
+    /// Reads and parses a TOML marker file, see [`VisibilityMarkers::from_toml_str`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Returns the marker of a single line of colored text.
+    ///
+    /// # Returns
+    /// - The marker of the foreground color of the visibility, the first run of letters
+    ///   or digits after the `:` that follows the item name.
+    /// - `None` if the line has no `:`, as the crate root, or if the color of the
+    ///   visibility has no marker.
+    ///
+    /// # Details
+    /// - The colors start out plain on every line, as cargo-modules resets them.
+    /// - The kind and name before the `:` are colored by kind, so their colors do not
+    ///   count; neither do tree connectors and other runs without letters or digits.
+    pub fn marker(&self, line: &str) -> Option<&str> {
+        let mut style = Style::default();
+        let mut after_colon = false;
+        for token in ansi::tokenize(line) {
+            match token {
+                Token::Escape(sequence) => {
+                    style.apply_sequence(&sequence);
+                }
+                Token::Text(text) => {
+                    let visibility = match after_colon {
+                        true => text,
+                        false => match text.split_once(':') {
+                            Some((_, rest)) => rest,
+                            None => continue,
+                        },
+                    };
+                    after_colon = true;
+                    if visibility.chars().any(char::is_alphanumeric) {
+                        return style.foreground.and_then(|color| self.get(color));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The contents of a TOML marker file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MarkerFile {
+    /// Whether the file builds on the default colors.
+    #[serde(default = "default_true")]
+    defaults: bool,
+    /// Markers keyed by a color name, a palette index or a `#rrggbb` color.
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Colors a line the way cargo-modules does: the kind blue, the name magenta and
+    /// the visibility in `color`.
+    fn line(item: &str, name: &str, color: &str, visibility: &str) -> String {
+        format!(
+            "│   ├── \u{1b}[1;34m{}\u{1b}[0m \u{1b}[35m{}\u{1b}[0m: \u{1b}[{}m{}\u{1b}[0m",
+            item, name, color, visibility
+        )
+    }
+
+    #[test]
+    fn marker_follows_the_color_of_the_visibility() {
+        let markers = VisibilityMarkers::new();
+        let cases = [
+            (line("fn", "run", "32", "pub"), Some("pub")),
+            (line("mod", "cli", "33", "pub(crate)"), Some("pub(crate)")),
+            (
+                line("fn", "helper", "38;2;254;109;0", "pub(in crate::cli)"),
+                Some("pub(in path)"),
+            ),
+            (line("fn", "private", "31", "pub(self)"), Some("pub(self)")),
+            (line("mod", "stale", "35", "orphan"), Some("orphan")),
+        ];
+        for (line, marker) in cases {
+            assert_eq!(markers.marker(&line), marker, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn name_and_kind_colors_are_ignored() {
+        let markers = VisibilityMarkers::new();
+        assert_eq!(
+            markers.marker("\u{1b}[1;34mcrate\u{1b}[0m \u{1b}[35mdemo\u{1b}[0m"),
+            None
+        );
+        assert_eq!(markers.marker(&line("fn", "run", "36", "pub")), None);
+        assert_eq!(markers.marker("├── fn run: pub"), None);
+        // The colon may share a run with the visibility
+        assert_eq!(
+            markers.marker("fn run\u{1b}[32m: pub\u{1b}[0m"),
+            Some("pub")
+        );
+    }
+
+    #[test]
+    fn marker_file_changes_the_table() {
+        let markers = VisibilityMarkers::from_toml_str(
+            "defaults = false\n[colors]\ncyan = \"pub\"\n\"#fe6d00\" = \"pub(super)\"\n",
+        )
+        .unwrap();
+        assert_eq!(markers.marker(&line("fn", "run", "36", "pub")), Some("pub"));
+        assert_eq!(markers.marker(&line("fn", "run", "32", "pub")), None);
+        assert_eq!(markers.get(Color::Rgb(254, 109, 0)), Some("pub(super)"));
+        assert!(VisibilityMarkers::from_toml_str("[colors]\nnot-a-color = \"pub\"\n").is_err());
+    }
+}
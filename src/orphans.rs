@@ -0,0 +1,132 @@
+//! Detection of orphaned source files and missing module files.
+//!
+//! The module trees of every target are extracted with the [`extract`](crate::extract)
+//! module and compared with the `.rs` files on disk under `src/`.
+
+use crate::extract::{self, MissingModule};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The result of comparing the module trees of a crate with its source files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrphanReport {
+    /// `.rs` files under `src/` that no target reaches through `mod` declarations.
+    pub orphans: Vec<PathBuf>,
+    /// `mod` declarations whose file does not exist.
+    pub missing: Vec<MissingModule>,
+}
+
+impl OrphanReport {
+    /// Returns `true` if there are neither orphaned files nor missing module files.
+    pub fn is_clean(&self) -> bool {
+        self.orphans.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Compares the module trees of a crate with the `.rs` files under its `src/` directory.
+///
+/// # Parameters
+/// - `crate_dir`: The crate directory, or the path of its `Cargo.toml`.
+///
+/// # Returns
+/// - `Ok(OrphanReport)` with paths as found on disk, sorted.
+/// - `Err(io::Error)` if a directory cannot be listed or a source file cannot be parsed.
+///
+/// # Details
+/// - Files reached by any target count as reachable, so `src/bin/*.rs` and modules
+///   shared between the library and a binary are not reported.
+/// - `#[cfg(test)]` modules are followed, so test-only files are not orphans.
+pub fn check(crate_dir: &Path) -> io::Result<OrphanReport> {
+    let extractions = extract::extract_targets(crate_dir, true)?;
+    let crate_dir = extract::manifest_dir(crate_dir);
+
+    let mut reached = Vec::new();
+    let mut report = OrphanReport::default();
+    for extraction in extractions {
+        for file in &extraction.files {
+            reached.push(fs::canonicalize(file)?);
+        }
+        report.missing.extend(extraction.missing);
+    }
+
+    let mut files = Vec::new();
+    let src_dir = crate_dir.join("src");
+    if src_dir.is_dir() {
+        collect_rust_files(&src_dir, &mut files)?;
+    }
+    for file in files {
+        if !reached.contains(&fs::canonicalize(&file)?) {
+            report.orphans.push(file);
+        }
+    }
+
+    report.orphans.sort();
+    // Targets sharing a file report its missing modules once per target
+    report
+        .missing
+        .sort_by(|a, b| (&a.path, &a.declared_in).cmp(&(&b.path, &b.declared_in)));
+    report.missing.dedup();
+    Ok(report)
+}
+
+/// Collects every `.rs` file below `dir`, recursively.
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_rust_files(&path, files)?;
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_module_of_a_shared_file_is_listed_once() {
+        let dir = std::env::temp_dir().join(format!("orphans-shared-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let files = [
+            (
+                "Cargo.toml",
+                "[package]\nname = \"shared\"\n\n[[bin]]\nname = \"tool\"\npath = \"src/tool.rs\"\n",
+            ),
+            ("src/lib.rs", "pub mod common;\n"),
+            ("src/common.rs", "mod gone;\n"),
+            // Same module path, declared in another file, sorts between the duplicates
+            ("src/main.rs", "mod common {\n    mod gone;\n}\nfn main() {}\n"),
+            (
+                "src/tool.rs",
+                "#[path = \"common.rs\"]\nmod common;\nfn main() {}\n",
+            ),
+        ];
+        for (file, source) in files {
+            fs::write(dir.join(file), source).unwrap();
+        }
+        let report = check(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let missing: Vec<(&str, &Path)> = report
+            .missing
+            .iter()
+            .map(|missing| {
+                let declared_in = missing.declared_in.strip_prefix(&dir).unwrap();
+                (missing.path.as_str(), declared_in)
+            })
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                ("crate::common::gone", Path::new("src/common.rs")),
+                ("crate::common::gone", Path::new("src/main.rs")),
+            ]
+        );
+        assert!(report.orphans.is_empty());
+    }
+}
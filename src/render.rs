@@ -0,0 +1,176 @@
+//! Text rendering of module trees in the layout of `cargo modules structure`.
+//!
+//! Trees are drawn with Unicode box-drawing connectors, exactly like cargo-modules
+//! does, so the result can be cleaned to ASCII with any preset like captured output.
+
+use crate::tree::{ModuleTree, Node};
+
+/// Renders the module tree as text with Unicode box-drawing connectors.
+///
+/// # Details
+/// - Each line holds `<kind> <name>`, followed by `: <visibility>` or `: orphan` and
+///   the item's attributes, the same layout [`ModuleTree::parse`] reads.
+/// - Items hidden by pruning are drawn as a `… (N items)` line after the children
+///   of their parent, which then is the parent's last child.
+pub fn to_text(tree: &ModuleTree) -> String {
+    to_text_with_ellipsis(tree, ELLIPSIS)
+}
+
+/// Renders the module tree like [`to_text`], starting placeholders with `ellipsis`.
+///
+/// # Details
+/// - Text that is cleaned to ASCII should pass [`ASCII_ELLIPSIS`], since cleaning
+///   only replaces box-drawing characters and would keep `…`.
+pub fn to_text_with_ellipsis(tree: &ModuleTree, ellipsis: &str) -> String {
+    let mut text = String::new();
+    draw(
+        &Line::new(&tree.root, ellipsis),
+        |line| &line.children,
+        |line, prefix| {
+            text.push_str(prefix);
+            text.push_str(&line.text);
+            text.push('\n');
+        },
+    );
+    text
+}
+
+/// The ellipsis that starts a placeholder, `…`.
+pub const ELLIPSIS: &str = "…";
+
+/// The ellipsis that starts a placeholder in ASCII text, `...`.
+pub const ASCII_ELLIPSIS: &str = "...";
+
+/// Returns the text of a placeholder for `count` hidden items, e.g. `… (3 items)`.
+pub fn placeholder_text(count: usize) -> String {
+    placeholder_text_with_ellipsis(count, ELLIPSIS)
+}
+
+/// Returns the text of a placeholder for `count` hidden items, starting with `ellipsis`.
+fn placeholder_text_with_ellipsis(count: usize, ellipsis: &str) -> String {
+    match count {
+        1 => format!("{} (1 item)", ellipsis),
+        count => format!("{} ({} items)", ellipsis, count),
+    }
+}
+
+/// A line of the text layout, with placeholders as children of their own.
+struct Line {
+    text: String,
+    children: Vec<Line>,
+}
+
+impl Line {
+    fn new(node: &Node, ellipsis: &str) -> Self {
+        let mut children: Vec<Line> = node
+            .children
+            .iter()
+            .map(|child| Line::new(child, ellipsis))
+            .collect();
+        if node.collapsed > 0 {
+            children.push(Line {
+                text: placeholder_text_with_ellipsis(node.collapsed, ellipsis),
+                children: Vec::new(),
+            });
+        }
+        Self {
+            text: item_text(node),
+            children,
+        }
+    }
+}
+
+/// Returns the text of a single item line without its tree prefix.
+pub(crate) fn item_text(node: &Node) -> String {
+    let mut text = format!("{} {}", node.kind, node.name);
+    if node.orphan {
+        text.push_str(": orphan");
+    } else if let Some(visibility) = &node.visibility {
+        text.push_str(": ");
+        text.push_str(&visibility.to_string());
+    }
+    for attribute in &node.attributes {
+        text.push(' ');
+        text.push_str(attribute);
+    }
+    text
+}
+
+/// Walks a tree in pre-order and calls `line` with each node and its connector prefix.
+///
+/// # Parameters
+/// - `root`: The root node, drawn without a prefix.
+/// - `children`: Returns the children of a node.
+/// - `line`: Called with each node and its prefix, e.g. `│   ├── `.
+///
+/// # Details
+/// - `└── ` marks the last child of a node and `├── ` every other child, so the
+///   connectors are always correct for the tree as passed in.
+pub(crate) fn draw<'a, T>(
+    root: &'a T,
+    children: impl Fn(&'a T) -> &'a [T],
+    mut line: impl FnMut(&'a T, &str),
+) {
+    line(root, "");
+    draw_children(root, &mut String::new(), &children, &mut line);
+}
+
+fn draw_children<'a, T>(
+    node: &'a T,
+    indent: &mut String,
+    children: &impl Fn(&'a T) -> &'a [T],
+    line: &mut impl FnMut(&'a T, &str),
+) {
+    let nodes = children(node);
+    for (index, child) in nodes.iter().enumerate() {
+        let last = index + 1 == nodes.len();
+        let prefix = format!("{}{}", indent, if last { "└── " } else { "├── " });
+        line(child, &prefix);
+
+        let indent_len = indent.len();
+        indent.push_str(if last { "    " } else { "│   " });
+        draw_children(child, indent, children, line);
+        indent.truncate(indent_len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{ItemKind, Visibility};
+
+    fn tree() -> ModuleTree {
+        let mut cli = Node::new(ItemKind::Mod, "cli");
+        cli.visibility = Some(Visibility::Crate);
+        cli.children = vec![Node::new(ItemKind::Fn, "run")];
+        cli.collapsed = 1;
+        let mut sys = Node::new(ItemKind::Mod, "sys");
+        sys.orphan = true;
+        sys.collapsed = 3;
+        let mut root = Node::new(ItemKind::Crate, "demo");
+        root.children = vec![cli, sys];
+        ModuleTree { root }
+    }
+
+    #[test]
+    fn placeholders_are_the_last_children() {
+        assert_eq!(
+            to_text(&tree()),
+            "crate demo\n\
+             ├── mod cli: pub(crate)\n\
+             │   ├── fn run\n\
+             │   └── … (1 item)\n\
+             └── mod sys: orphan\n    \
+                 └── … (3 items)\n"
+        );
+    }
+
+    #[test]
+    fn ascii_placeholders_are_read_back() {
+        let text = to_text_with_ellipsis(&tree(), ASCII_ELLIPSIS);
+        assert!(text.contains("└── ... (3 items)\n"));
+        assert!(!text.contains('…'));
+        assert_eq!(ModuleTree::parse(&text).unwrap(), tree());
+        assert_eq!(ModuleTree::parse(&to_text(&tree())).unwrap(), tree());
+    }
+}
@@ -0,0 +1,354 @@
+//! Interpretation of SGR (Select Graphic Rendition) escape sequences.
+//!
+//! SGR sequences such as `ESC[1;32m` set the colors and attributes of the text that
+//! follows. A [`Style`] tracks that state while a line is read, so colors can be
+//! turned into HTML or into textual markers instead of being discarded.
+
+use crate::ansi::{EscapeSequence, SequenceKind};
+
+/// A foreground or background color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// An entry of the 256-color palette; 0 to 7 are the standard colors and 8 to 15
+    /// their bright variants.
+    Indexed(u8),
+    /// A truecolor value.
+    Rgb(u8, u8, u8),
+}
+
+/// The 16 standard and bright colors, as xterm shows them.
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Names of the 8 standard colors; the bright variants add a `bright-` prefix.
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl Color {
+    /// Returns the color as red, green and blue components.
+    ///
+    /// # Details
+    /// - Palette entries 16 to 231 form a 6×6×6 color cube and 232 to 255 a gray ramp,
+    ///   as in xterm.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(red, green, blue) => (red, green, blue),
+            Color::Indexed(index @ 0..=15) => PALETTE[index as usize],
+            Color::Indexed(index @ 16..=231) => {
+                let level = |value: u8| match value {
+                    0 => 0,
+                    value => 55 + value * 40,
+                };
+                let index = index - 16;
+                (level(index / 36), level(index / 6 % 6), level(index % 6))
+            }
+            Color::Indexed(index) => {
+                let gray = 8 + (index - 232) * 10;
+                (gray, gray, gray)
+            }
+        }
+    }
+
+    /// Returns the color as a CSS hex color, e.g. `#00cd00`.
+    pub fn to_css(self) -> String {
+        let (red, green, blue) = self.to_rgb();
+        format!("#{:02x}{:02x}{:02x}", red, green, blue)
+    }
+
+    /// Returns the name of one of the 16 standard colors, e.g. `green` or `bright-red`.
+    ///
+    /// # Returns
+    /// - `None` for other palette entries and truecolor values.
+    pub fn name(self) -> Option<String> {
+        match self {
+            Color::Indexed(index @ 0..=7) => Some(NAMES[index as usize].to_string()),
+            Color::Indexed(index @ 8..=15) => Some(format!("bright-{}", NAMES[index as usize - 8])),
+            _ => None,
+        }
+    }
+
+    /// Parses a color name as returned by [`Color::name`], a palette index such as
+    /// `208`, or a hex color such as `#fe6d00`.
+    ///
+    /// # Returns
+    /// - `None` if the text is none of these.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix('#') {
+            let value = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6)?;
+            let [_, red, green, blue] = value.to_be_bytes();
+            return Some(Color::Rgb(red, green, blue));
+        }
+        if let Ok(index) = text.parse() {
+            return Some(Color::Indexed(index));
+        }
+        let (name, offset) = match text.strip_prefix("bright-") {
+            Some(name) => (name, 8),
+            None => (text, 0),
+        };
+        let index = NAMES.iter().position(|candidate| *candidate == name)?;
+        Some(Color::Indexed(index as u8 + offset))
+    }
+}
+
+/// The graphic rendition in effect at some point of the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    /// The foreground color; `None` for the terminal default.
+    pub foreground: Option<Color>,
+    /// The background color; `None` for the terminal default.
+    pub background: Option<Color>,
+    /// Bold or increased intensity.
+    pub bold: bool,
+    /// Italic.
+    pub italic: bool,
+    /// Underlined, singly or otherwise.
+    pub underline: bool,
+}
+
+impl Style {
+    /// Returns `true` if no color or attribute is set.
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// Updates the style with an escape sequence.
+    ///
+    /// # Returns
+    /// - `true` if the sequence is an SGR sequence and was applied.
+    /// - `false` for any other sequence, which leaves the style unchanged.
+    pub fn apply_sequence(&mut self, sequence: &EscapeSequence<'_>) -> bool {
+        match sgr_parameters(sequence) {
+            Some(parameters) => {
+                self.apply(parameters);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Updates the style with the parameters of an SGR sequence, e.g. `1;38;5;208`.
+    ///
+    /// # Details
+    /// - Empty parameters count as 0, which resets the style.
+    /// - Extended colors are accepted with `;` or `:` separators, e.g. `38;2;r;g;b`
+    ///   or `38:2::r:g:b`.
+    /// - Unsupported attributes such as blinking are ignored.
+    pub fn apply(&mut self, parameters: &str) {
+        let parameters: Vec<&str> = parameters.split(';').collect();
+        let mut index = 0;
+        while index < parameters.len() {
+            let parameter = parameters[index];
+            index += 1;
+            if parameter.contains(':') {
+                let values: Vec<&str> = parameter.split(':').collect();
+                match number(values[0]) {
+                    code @ (38 | 48) => {
+                        // The color space identifier of `38:2:<id>:r:g:b` is optional
+                        let values = match values.get(1) {
+                            Some(&"2") if values.len() > 5 => {
+                                [&values[1..2], &values[3..]].concat()
+                            }
+                            _ => values[1..].to_vec(),
+                        };
+                        self.set_color(code, extended_color(&values).0);
+                    }
+                    4 => self.underline = values.get(1).is_none_or(|style| number(style) != 0),
+                    code => self.apply_code(code),
+                }
+                continue;
+            }
+
+            match number(parameter) {
+                code @ (38 | 48 | 58) => {
+                    let (color, used) = extended_color(&parameters[index..]);
+                    index += used;
+                    if code != 58 {
+                        self.set_color(code, color);
+                    }
+                }
+                code => self.apply_code(code),
+            }
+        }
+    }
+
+    fn set_color(&mut self, code: u16, color: Option<Color>) {
+        match code {
+            38 => self.foreground = color.or(self.foreground),
+            _ => self.background = color.or(self.background),
+        }
+    }
+
+    fn apply_code(&mut self, code: u16) {
+        match code {
+            0 => *self = Style::default(),
+            1 => self.bold = true,
+            3 => self.italic = true,
+            4 | 21 => self.underline = true,
+            22 => self.bold = false,
+            23 => self.italic = false,
+            24 => self.underline = false,
+            30..=37 => self.foreground = Some(Color::Indexed(code as u8 - 30)),
+            39 => self.foreground = None,
+            40..=47 => self.background = Some(Color::Indexed(code as u8 - 40)),
+            49 => self.background = None,
+            90..=97 => self.foreground = Some(Color::Indexed(code as u8 - 90 + 8)),
+            100..=107 => self.background = Some(Color::Indexed(code as u8 - 100 + 8)),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the parameters of an SGR sequence, e.g. `1;32` for `ESC[1;32m`.
+///
+/// # Returns
+/// - `None` if the sequence is not a terminated CSI sequence ending in `m`, or if it
+///   has private parameters or intermediate bytes.
+pub fn sgr_parameters<'a>(sequence: &EscapeSequence<'a>) -> Option<&'a str> {
+    if sequence.kind != SequenceKind::Csi || !sequence.terminated {
+        return None;
+    }
+    let body = sequence
+        .raw
+        .strip_prefix("\u{1b}[")
+        .or_else(|| sequence.raw.strip_prefix('\u{9b}'))?;
+    let parameters = body.strip_suffix('m')?;
+    parameters
+        .bytes()
+        .all(|byte| byte.is_ascii_digit() || byte == b';' || byte == b':')
+        .then_some(parameters)
+}
+
+/// Parses the color after `38`, `48` or `58`: `5;<index>` or `2;<r>;<g>;<b>`.
+///
+/// # Returns
+/// - The color, if valid, and the number of parameters it took.
+fn extended_color(values: &[&str]) -> (Option<Color>, usize) {
+    let component = |index: usize| values.get(index).map(|value| number(value).min(255) as u8);
+    match values.first().map(|value| number(value)) {
+        Some(5) => (component(1).map(Color::Indexed), values.len().min(2)),
+        Some(2) => match (component(1), component(2), component(3)) {
+            (Some(red), Some(green), Some(blue)) => (Some(Color::Rgb(red, green, blue)), 4),
+            _ => (None, values.len()),
+        },
+        Some(_) => (None, 1),
+        None => (None, 0),
+    }
+}
+
+/// Parses an SGR parameter; empty and malformed parameters count as 0.
+fn number(parameter: &str) -> u16 {
+    parameter.parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::{self, Token};
+
+    fn style(parameters: &str) -> Style {
+        let mut style = Style::default();
+        style.apply(parameters);
+        style
+    }
+
+    #[test]
+    fn attributes_are_set_and_reset() {
+        let bold = style("1;3;4");
+        assert!(bold.bold && bold.italic && bold.underline);
+        assert_eq!(style("1;3;4;22;23;24"), Style::default());
+        assert_eq!(style("1;31;0"), Style::default());
+        assert_eq!(style(""), Style::default());
+        assert!(style("4:3").underline);
+        assert!(!style("4;4:0").underline);
+    }
+
+    #[test]
+    fn standard_and_bright_colors() {
+        let red = style("31;42");
+        assert_eq!(red.foreground, Some(Color::Indexed(1)));
+        assert_eq!(red.background, Some(Color::Indexed(2)));
+        let bright = style("91;103");
+        assert_eq!(bright.foreground, Some(Color::Indexed(9)));
+        assert_eq!(bright.background, Some(Color::Indexed(11)));
+        assert_eq!(style("31;39").foreground, None);
+        assert_eq!(style("42;49").background, None);
+    }
+
+    #[test]
+    fn extended_colors() {
+        assert_eq!(style("38;5;208").foreground, Some(Color::Indexed(208)));
+        assert_eq!(style("48;5;17").background, Some(Color::Indexed(17)));
+        assert_eq!(
+            style("38;2;254;109;0").foreground,
+            Some(Color::Rgb(254, 109, 0))
+        );
+        assert_eq!(
+            style("38:2::254:109:0").foreground,
+            Some(Color::Rgb(254, 109, 0))
+        );
+        assert_eq!(
+            style("38:2:254:109:0").foreground,
+            Some(Color::Rgb(254, 109, 0))
+        );
+        assert_eq!(style("48:5:208").background, Some(Color::Indexed(208)));
+        // The underline color is skipped together with its parameters
+        assert_eq!(style("58;5;1;1"), style("1"));
+        // Parameters after an extended color still apply
+        let both = style("38;5;2;1");
+        assert_eq!(both.foreground, Some(Color::Indexed(2)));
+        assert!(both.bold);
+    }
+
+    #[test]
+    fn only_sgr_sequences_are_applied() {
+        let sequences: Vec<_> = ansi::tokenize("\u{1b}[1;32m\u{9b}0m\u{1b}[?25l\u{1b}[2K\u{1b}[1m")
+            .filter_map(|token| match token {
+                Token::Escape(sequence) => Some(sequence),
+                Token::Text(_) => None,
+            })
+            .collect();
+        let parameters: Vec<_> = sequences.iter().map(sgr_parameters).collect();
+        assert_eq!(parameters, [Some("1;32"), Some("0"), None, None, Some("1")]);
+
+        let mut style = Style::default();
+        assert!(style.apply_sequence(&sequences[0]));
+        assert!(!style.apply_sequence(&sequences[2]));
+        assert_eq!(style.foreground, Some(Color::Indexed(2)));
+    }
+
+    #[test]
+    fn colors_convert_to_rgb_names_and_css() {
+        assert_eq!(Color::Indexed(2).to_css(), "#00cd00");
+        assert_eq!(Color::Indexed(16).to_rgb(), (0, 0, 0));
+        assert_eq!(Color::Indexed(208).to_rgb(), (255, 135, 0));
+        assert_eq!(Color::Indexed(232).to_rgb(), (8, 8, 8));
+        assert_eq!(Color::Indexed(255).to_rgb(), (238, 238, 238));
+        assert_eq!(Color::Indexed(9).name().as_deref(), Some("bright-red"));
+        assert_eq!(Color::Indexed(208).name(), None);
+        assert_eq!(Color::parse("bright-red"), Some(Color::Indexed(9)));
+        assert_eq!(Color::parse(" 208 "), Some(Color::Indexed(208)));
+        assert_eq!(Color::parse("#fe6d00"), Some(Color::Rgb(254, 109, 0)));
+        assert_eq!(Color::parse("#fe6d0"), None);
+        assert_eq!(Color::parse("purple"), None);
+    }
+}
@@ -0,0 +1,554 @@
+//! Typed module tree parsed from `cargo modules structure` output.
+//!
+//! The parser accepts both the raw output, with colors and Unicode box-drawing
+//! characters, and output that was already cleaned to ASCII with any preset:
+//!
+//! ```text
+//! crate my_crate
+//! ├── mod cli: pub(crate)
+//! │   └── struct Cli: pub
+//! └── mod tests: pub(self) #[cfg(test)]
+//! ```
+
+use crate::Cleaner;
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// The kind of an item in the module tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ItemKind {
+    /// The crate root.
+    Crate,
+    /// A module.
+    Mod,
+    /// A struct.
+    Struct,
+    /// An enum.
+    Enum,
+    /// A union.
+    Union,
+    /// A trait.
+    Trait,
+    /// A function.
+    Fn,
+    /// A type alias.
+    Type,
+    /// A constant.
+    Const,
+    /// A static.
+    Static,
+    /// A macro.
+    Macro,
+    /// Any other kind, as printed by cargo-modules.
+    Other(String),
+}
+
+impl ItemKind {
+    /// The keywords of every kind except [`ItemKind::Other`].
+    pub const KEYWORDS: &'static [&'static str] = &[
+        "crate", "mod", "struct", "enum", "union", "trait", "fn", "type", "const", "static",
+        "macro",
+    ];
+
+    /// Returns the keyword of the kind as printed by cargo-modules, e.g. `"struct"`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ItemKind::Crate => "crate",
+            ItemKind::Mod => "mod",
+            ItemKind::Struct => "struct",
+            ItemKind::Enum => "enum",
+            ItemKind::Union => "union",
+            ItemKind::Trait => "trait",
+            ItemKind::Fn => "fn",
+            ItemKind::Type => "type",
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+            ItemKind::Macro => "macro",
+            ItemKind::Other(keyword) => keyword,
+        }
+    }
+
+    /// Parses the keyword of a kind. Unknown keywords become [`ItemKind::Other`].
+    pub fn from_keyword(keyword: &str) -> Self {
+        match keyword {
+            "crate" => ItemKind::Crate,
+            "mod" => ItemKind::Mod,
+            "struct" => ItemKind::Struct,
+            "enum" => ItemKind::Enum,
+            "union" => ItemKind::Union,
+            "trait" => ItemKind::Trait,
+            "fn" => ItemKind::Fn,
+            "type" => ItemKind::Type,
+            "const" => ItemKind::Const,
+            "static" => ItemKind::Static,
+            "macro" => ItemKind::Macro,
+            other => ItemKind::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The visibility of an item in the module tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Visibility {
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(super)`
+    Super,
+    /// `pub(in path)`, holding the path.
+    Restricted(String),
+    /// `pub(self)`, i.e. private.
+    Private,
+}
+
+impl Visibility {
+    /// Parses a visibility as printed by cargo-modules, e.g. `"pub(crate)"`.
+    ///
+    /// # Returns
+    /// - `Some(Visibility)` for a valid visibility.
+    /// - `None` for any other text.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "pub" => Some(Visibility::Public),
+            "pub(crate)" => Some(Visibility::Crate),
+            "pub(super)" => Some(Visibility::Super),
+            "pub(self)" => Some(Visibility::Private),
+            _ => text
+                .strip_prefix("pub(in ")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .map(|path| Visibility::Restricted(path.trim().to_string())),
+        }
+    }
+
+    /// Returns `true` for `pub`.
+    pub fn is_public(&self) -> bool {
+        matches!(self, Visibility::Public)
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Visibility::Public => f.write_str("pub"),
+            Visibility::Crate => f.write_str("pub(crate)"),
+            Visibility::Super => f.write_str("pub(super)"),
+            Visibility::Restricted(path) => write!(f, "pub(in {})", path),
+            Visibility::Private => f.write_str("pub(self)"),
+        }
+    }
+}
+
+/// A node of the module tree: the crate root, a module or an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// The name of the item, e.g. `cli` or `r#fn`.
+    pub name: String,
+    /// The kind of the item.
+    pub kind: ItemKind,
+    /// The visibility of the item, `None` for the crate root and orphans.
+    pub visibility: Option<Visibility>,
+    /// Attributes printed after the item, e.g. `#[cfg(test)]`.
+    pub attributes: Vec<String>,
+    /// `true` if cargo-modules marked the module as an orphan file.
+    pub orphan: bool,
+    /// The child items, in output order.
+    pub children: Vec<Node>,
+    /// The number of items hidden below this node by depth limiting or collapsing,
+    /// printed as a `… (N items)` placeholder after the children.
+    pub collapsed: usize,
+}
+
+impl Node {
+    /// Creates a node without visibility, attributes or children.
+    pub fn new(kind: ItemKind, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            visibility: None,
+            attributes: Vec::new(),
+            orphan: false,
+            children: Vec::new(),
+            collapsed: 0,
+        }
+    }
+
+    /// Returns the path of a child of this node, given this node's path.
+    ///
+    /// # Details
+    /// - The crate root has the path `crate`, its children `crate::name`.
+    pub fn child_path(path: &str, child: &Node) -> String {
+        format!("{}::{}", path, child.name)
+    }
+
+    /// Returns the number of items below this node, including hidden ones.
+    pub fn item_count(&self) -> usize {
+        self.collapsed
+            + self
+                .children
+                .iter()
+                .map(|child| 1 + child.item_count())
+                .sum::<usize>()
+    }
+
+    /// Returns the number of nodes below this node.
+    pub fn descendant_count(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| 1 + child.descendant_count())
+            .sum()
+    }
+}
+
+/// A module tree with a single crate root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleTree {
+    /// The crate root.
+    pub root: Node,
+}
+
+impl ModuleTree {
+    /// Parses `cargo modules structure` output, raw or cleaned.
+    ///
+    /// # Parameters
+    /// - `text`: The complete output.
+    ///
+    /// # Returns
+    /// - `Ok(ModuleTree)` if the output contains exactly one crate root.
+    /// - `Err(io::Error)` of kind `InvalidData` with the offending line number otherwise.
+    ///
+    /// # Details
+    /// - Lines before the crate root, such as cargo progress messages, are skipped.
+    /// - The tree ends at the first unindented line that is not a crate root, so
+    ///   output that follows it, such as `warning:` lines captured with `2>&1`, is
+    ///   skipped as well.
+    /// - The indentation width of one level is taken from the first indented line, so
+    ///   output cleaned with any preset or mapping file can be parsed.
+    /// - `… (N items)` placeholders written for pruned trees are read back into
+    ///   [`Node::collapsed`].
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let cleaned = Cleaner::new().clean_str(text);
+        let mut stack: Vec<Node> = Vec::new();
+        let mut unit = None;
+
+        for (index, line) in cleaned.lines().enumerate() {
+            let line_number = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let placeholder = parse_placeholder(line);
+            let (indent, item) = match placeholder {
+                Some((indent, _)) => (indent, ""),
+                None => split_prefix(line),
+            };
+            if stack.is_empty() {
+                // Anything before the crate root is not part of the tree
+                let root = parse_item(item).filter(|node| node.kind == ItemKind::Crate);
+                if let Some(root) = root.filter(|_| indent == 0) {
+                    stack.push(root);
+                }
+                continue;
+            }
+
+            if indent == 0 {
+                let second_root = parse_item(item).is_some_and(|node| node.kind == ItemKind::Crate);
+                if second_root {
+                    return Err(invalid_line(line_number, "unexpected second crate root"));
+                }
+                // The tree has ended; the rest is other output such as cargo warnings
+                break;
+            }
+            let unit = *unit.get_or_insert(indent);
+            if indent % unit != 0 {
+                return Err(invalid_line(line_number, "inconsistent indentation"));
+            }
+            let depth = indent / unit;
+            if depth > stack.len() {
+                return Err(invalid_line(line_number, "item is indented too deeply"));
+            }
+            let node = match placeholder {
+                Some(_) => None,
+                None => Some(
+                    parse_item(item)
+                        .ok_or_else(|| invalid_line(line_number, "expected `<kind> <name>`"))?,
+                ),
+            };
+
+            // Close every open node at this depth or deeper
+            while stack.len() > depth {
+                let finished = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(finished);
+            }
+            match (node, placeholder) {
+                (Some(node), _) => stack.push(node),
+                (None, Some((_, count))) => stack.last_mut().unwrap().collapsed += count,
+                (None, None) => unreachable!("every line is an item or a placeholder"),
+            }
+        }
+
+        while stack.len() > 1 {
+            let finished = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(finished);
+        }
+        stack
+            .pop()
+            .map(|root| ModuleTree { root })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no crate root found"))
+    }
+
+    /// Reads and parses `cargo modules structure` output from `reader`.
+    ///
+    /// # Details
+    /// - The input is read with [`Cleaner::read_text`], so raw 8-bit C1 controls are
+    ///   accepted and invalid UTF-8 is reported with its position.
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+        Self::parse(&Cleaner::new().read_text(reader)?)
+    }
+
+    /// Returns every node with its path and depth, in depth-first pre-order.
+    ///
+    /// # Details
+    /// - The crate root has depth 0 and the path `crate`.
+    pub fn nodes(&self) -> Vec<(String, usize, &Node)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![("crate".to_string(), 0, &self.root)];
+        while let Some((path, depth, node)) = stack.pop() {
+            for child in node.children.iter().rev() {
+                stack.push((Node::child_path(&path, child), depth + 1, child));
+            }
+            nodes.push((path, depth, node));
+        }
+        nodes
+    }
+}
+
+/// Splits a cleaned line into the width of its tree prefix and the item text.
+///
+/// # Details
+/// - The prefix consists of whitespace and connector characters. It ends at the
+///   first letter, which starts the kind keyword of the item.
+fn split_prefix(line: &str) -> (usize, &str) {
+    match line.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((index, _)) => (line[..index].chars().count(), &line[index..]),
+        None => (line.chars().count(), ""),
+    }
+}
+
+/// Parses a `… (N items)` placeholder line, also accepting `...` for the ellipsis.
+///
+/// # Returns
+/// - `Some((indent, N))` for a placeholder line, `indent` being the width of its prefix.
+/// - `None` for any other line.
+fn parse_placeholder(line: &str) -> Option<(usize, usize)> {
+    let text = line.trim_end();
+    let (prefix, rest) = text
+        .rsplit_once("… (")
+        .or_else(|| text.rsplit_once("... ("))?;
+    let count = rest
+        .strip_suffix(" items)")
+        .or_else(|| rest.strip_suffix(" item)"))?;
+    if prefix.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    Some((prefix.chars().count(), count.parse().ok()?))
+}
+
+/// Parses the item text of a line: `<kind> <name>[: <visibility>] [attributes]`.
+///
+/// # Returns
+/// - `Some(Node)` without children.
+/// - `None` if the text does not contain a kind and a name.
+fn parse_item(item: &str) -> Option<Node> {
+    let (keyword, rest) = item.trim().split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+
+    // The name ends at the visibility separator or at the first space
+    let name_end = rest
+        .find(|c: char| c == ':' || c.is_whitespace())
+        .unwrap_or(rest.len());
+    let name = &rest[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut node = Node::new(ItemKind::from_keyword(keyword), name);
+    let mut rest = rest[name_end..].trim_start();
+    rest = rest.strip_prefix(':').unwrap_or(rest).trim_start();
+
+    while !rest.is_empty() {
+        let token_end = token_end(rest);
+        let token = &rest[..token_end];
+        if token == "orphan" {
+            node.orphan = true;
+        } else if let Some(visibility) = Visibility::parse(token) {
+            node.visibility = Some(visibility);
+        } else {
+            node.attributes.push(token.to_string());
+        }
+        rest = rest[token_end..].trim_start();
+    }
+    Some(node)
+}
+
+/// Returns the length of the first token of `text`.
+///
+/// # Details
+/// - Tokens are separated by whitespace, except inside brackets and parentheses, so
+///   `#[cfg(all(test, unix))]` and `pub(in crate::a)` are single tokens.
+fn token_end(text: &str) -> usize {
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => return index,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn invalid_line(line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapping::CharMap;
+
+    const RAW: &str = "\
+\u{1b}[1;34mcrate\u{1b}[0m \u{1b}[35mdemo\u{1b}[0m
+├── \u{1b}[1;34mmod\u{1b}[0m \u{1b}[35mcli\u{1b}[0m: \u{1b}[33mpub(crate)\u{1b}[0m
+│   └── \u{1b}[1;34mstruct\u{1b}[0m \u{1b}[35mCli\u{1b}[0m: \u{1b}[32mpub\u{1b}[0m
+└── \u{1b}[1;34mmod\u{1b}[0m \u{1b}[35mtests\u{1b}[0m: \u{1b}[31mpub(self)\u{1b}[0m #[cfg(test)]
+    └── \u{1b}[1;34mfn\u{1b}[0m \u{1b}[35mit_works\u{1b}[0m: \u{1b}[31mpub(self)\u{1b}[0m
+";
+
+    fn summary(tree: &ModuleTree) -> Vec<String> {
+        tree.nodes()
+            .into_iter()
+            .map(|(path, depth, node)| {
+                let visibility = node
+                    .visibility
+                    .as_ref()
+                    .map(Visibility::to_string)
+                    .unwrap_or_default();
+                format!("{} {} {} {}", depth, node.kind, path, visibility)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn expected() -> Vec<&'static str> {
+        vec![
+            "0 crate crate",
+            "1 mod crate::cli pub(crate)",
+            "2 struct crate::cli::Cli pub",
+            "1 mod crate::tests pub(self)",
+            "2 fn crate::tests::it_works pub(self)",
+        ]
+    }
+
+    #[test]
+    fn parses_raw_colored_output() {
+        let tree = ModuleTree::parse(RAW).unwrap();
+        assert_eq!(tree.root.name, "demo");
+        assert_eq!(summary(&tree), expected());
+        assert_eq!(tree.root.children[1].attributes, ["#[cfg(test)]"]);
+    }
+
+    #[test]
+    fn parses_output_cleaned_with_every_preset() {
+        for preset in crate::mapping::PRESETS {
+            let cleaner = Cleaner::new().mapping(CharMap::preset(preset).unwrap());
+            let cleaned = cleaner.clean_str(RAW);
+            let tree = ModuleTree::parse(&cleaned).unwrap();
+            assert_eq!(summary(&tree), expected(), "{}:\n{}", preset, cleaned);
+        }
+    }
+
+    #[test]
+    fn parses_tree_classic_layout() {
+        let text = "crate demo\n|-- mod a: pub\n|   `-- fn f: pub(super)\n`-- fn g: pub(self)\n";
+        let tree = ModuleTree::parse(text).unwrap();
+        assert_eq!(
+            summary(&tree),
+            [
+                "0 crate crate",
+                "1 mod crate::a pub",
+                "2 fn crate::a::f pub(super)",
+                "1 fn crate::g pub(self)",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_restricted_visibility_and_nested_attributes() {
+        let text = "crate demo\n\
+                    └── mod a: pub(in crate::b) #[cfg(all(test, unix))] #[doc(hidden)]\n";
+        let tree = ModuleTree::parse(text).unwrap();
+        let node = &tree.root.children[0];
+        assert_eq!(
+            node.visibility,
+            Some(Visibility::Restricted("crate::b".to_string()))
+        );
+        assert_eq!(
+            node.attributes,
+            ["#[cfg(all(test, unix))]", "#[doc(hidden)]"]
+        );
+    }
+
+    #[test]
+    fn parses_orphans_and_placeholders() {
+        let text = "crate demo\n\
+                    ├── mod stale: orphan\n\
+                    └── mod big: pub\n    \
+                        ├── fn first: pub\n    \
+                        └── … (12 items)\n";
+        let tree = ModuleTree::parse(text).unwrap();
+        let stale = &tree.root.children[0];
+        assert!(stale.orphan);
+        assert_eq!(stale.visibility, None);
+        let big = &tree.root.children[1];
+        assert_eq!(big.children.len(), 1);
+        assert_eq!(big.collapsed, 12);
+        assert_eq!(tree.root.item_count(), 15);
+
+        let ascii = ModuleTree::parse(&text.replace('…', "...")).unwrap();
+        assert_eq!(ascii, tree);
+    }
+
+    #[test]
+    fn skips_output_around_the_tree() {
+        let text = "   Compiling demo v0.1.0\n\
+                    crate demo\n\
+                    └── fn main: pub(crate)\n\
+                    warning: unused variable: `x`\n  \
+                      --> src/main.rs:2:9\n   \
+                       |\n";
+        let tree = ModuleTree::parse(text).unwrap();
+        assert_eq!(
+            summary(&tree),
+            ["0 crate crate", "1 fn crate::main pub(crate)"]
+        );
+    }
+
+    #[test]
+    fn rejects_a_second_crate_root() {
+        let error = ModuleTree::parse("crate a\n└── fn f\ncrate b\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("line 3"), "{}", error);
+    }
+}
@@ -0,0 +1,107 @@
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+/// Quiet period after the last change before a rebuild starts.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches files and directories and calls `rebuild` with the files that changed.
+///
+/// # Parameters
+/// - `inputs`: The files and directories to watch.
+/// - `known`: Files that are up to date now; they are only rebuilt once modified.
+/// - `recursive`: Whether changes anywhere below a watched directory count.
+/// - `rebuild`: Called with the changed files, sorted and deduplicated, once no
+///   change was seen for [`DEBOUNCE`].
+///
+/// # Returns
+/// - `Err(io::Error)` if a path cannot be watched. Otherwise watches until the process
+///   is terminated.
+///
+/// # Details
+/// - Files are watched through their parent directory, so editors that save by
+///   renaming a new file over the old one keep triggering rebuilds.
+/// - A file counts as changed when its modification time differs from the last one
+///   seen, so merely reading a file, or rewriting it with its time restored as
+///   `--in-place` does, triggers no rebuild.
+/// - Paths passed to `rebuild` are canonical.
+pub fn watch(
+    inputs: &[PathBuf],
+    known: &[PathBuf],
+    recursive: bool,
+    mut rebuild: impl FnMut(Vec<PathBuf>),
+) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer = new_debouncer(DEBOUNCE, sender).map_err(io::Error::other)?;
+
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for input in inputs {
+        let input = fs::canonicalize(input)?;
+        if input.is_dir() {
+            let mode = match recursive {
+                true => RecursiveMode::Recursive,
+                false => RecursiveMode::NonRecursive,
+            };
+            debouncer
+                .watcher()
+                .watch(&input, mode)
+                .map_err(io::Error::other)?;
+            dirs.push(input);
+        } else {
+            let parent = input.parent().unwrap_or(Path::new("/"));
+            debouncer
+                .watcher()
+                .watch(parent, RecursiveMode::NonRecursive)
+                .map_err(io::Error::other)?;
+            files.push(input);
+        }
+    }
+
+    let mut modified = HashMap::new();
+    for file in known {
+        let file = fs::canonicalize(file)?;
+        modified.insert(file.clone(), modified_time(&file));
+    }
+
+    let watched = |path: &Path| {
+        files.iter().any(|file| file == path)
+            || dirs.iter().any(|dir| match recursive {
+                true => path.starts_with(dir),
+                false => path.parent() == Some(dir.as_path()),
+            })
+    };
+    for result in receiver {
+        match result {
+            Ok(events) => {
+                let mut changed: Vec<PathBuf> = events
+                    .into_iter()
+                    .map(|event| event.path)
+                    .filter(|path| path.is_file() && watched(path))
+                    .collect();
+                changed.sort();
+                changed.dedup();
+                changed.retain(|path| {
+                    let time = modified_time(path);
+                    modified.insert(path.clone(), time) != Some(time)
+                });
+                if !changed.is_empty() {
+                    rebuild(changed);
+                }
+            }
+            Err(error) => eprintln!("watch error: {}", error),
+        }
+    }
+    Ok(())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}